@@ -0,0 +1,193 @@
+// What each key and controller button does. Bindings files list an action per
+// line followed by the inputs triggering it, separated by commas:
+//
+//     a = key:Z, pad:a
+//     screenshot = key:F12
+//     raw-screenshot = key:Shift+F12
+//     stick-deadzone = 8000
+//     turbo-rate = 2 2
+//
+// Keys go by their SDL names, controller buttons by their SDL game controller
+// names (a, b, x, y, back, start, dpup, leftshoulder...). Actions in the file
+// replace all of their default bindings, and the left stick works as a d-pad
+// once tilted past stick-deadzone, out of 32767 (0 turns that off). Turbo
+// buttons alternate between being held for as many frames as the first
+// number in turbo-rate and released for the second.
+use crate::mmu::io::joypad::Button;
+use sdl2::{controller, keyboard::Keycode};
+use snafu::Snafu;
+use std::collections::HashMap;
+
+const DEFAULTS: &str = "
+a = key:Z, pad:a
+b = key:X, pad:b
+start = key:Return, pad:start
+select = key:Backspace, pad:back
+up = key:Up, pad:dpup
+down = key:Down, pad:dpdown
+left = key:Left, pad:dpleft
+right = key:Right, pad:dpright
+turbo-a = key:A, pad:x
+turbo-b = key:S, pad:y
+
+quit = key:Escape
+toggle-background = key:F1
+toggle-window = key:F2
+toggle-sprites = key:F3
+tint-layers = key:F4
+next-palette = key:F5
+next-filter = key:F6
+ghosting = key:F7
+record = key:F9
+record-gif = key:Shift+F9
+stop-movie = key:F10
+screenshot = key:F12
+raw-screenshot = key:Shift+F12
+
+stick-deadzone = 0
+turbo-rate = 2 2
+";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Hotkey {
+    QUIT,
+    TOGGLEBACKGROUND,
+    TOGGLEWINDOW,
+    TOGGLESPRITES,
+    TINTLAYERS,
+    NEXTPALETTE,
+    NEXTFILTER,
+    GHOSTING,
+    RECORD,
+    RECORDGIF,
+    STOPMOVIE,
+    SCREENSHOT,
+    RAWSCREENSHOT,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Action {
+    BUTTON(Button),
+    TURBO(Button),
+    HOTKEY(Hotkey),
+}
+
+#[derive(Snafu, Debug)]
+pub enum BindingError {
+    #[snafu(display("Could not read bindings file {}: {}", path, source))]
+    Read { path: String, source: std::io::Error },
+
+    #[snafu(display("Bindings line {}: {}", line, msg))]
+    Syntax { line: usize, msg: String },
+}
+
+pub struct Bindings {
+    keys: HashMap<(Keycode, bool), Action>, // key, with shift held or not
+    pad: HashMap<controller::Button, Action>,
+    pub stick_deadzone: i16,
+    pub turbo_rate: (u32, u32), // frames held, frames released
+}
+
+impl Bindings {
+    // The defaults, overridden by the bindings file when there is one
+    pub fn load(path: Option<&str>) -> Result<Self, BindingError> {
+        let mut bindings = Self { keys: HashMap::new(), pad: HashMap::new(), stick_deadzone: 0, turbo_rate: (1, 1) };
+        bindings.parse(DEFAULTS).unwrap();
+        if let Some(path) = path {
+            let content = std::fs::read_to_string(path)
+                .map_err(|source| BindingError::Read { path: path.to_string(), source })?;
+            bindings.parse(&content)?;
+        }
+        Ok(bindings)
+    }
+
+    pub fn key(&self, key: Keycode, shift: bool) -> Option<Action> {
+        self.keys.get(&(key, shift)).copied()
+    }
+
+    pub fn pad(&self, button: controller::Button) -> Option<Action> {
+        self.pad.get(&button).copied()
+    }
+
+    fn parse(&mut self, content: &str) -> Result<(), BindingError> {
+        for (i, line) in content.lines().enumerate() {
+            let syntax = |msg: String| BindingError::Syntax { line: i + 1, msg };
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (name, inputs) =
+                line.split_once('=').ok_or_else(|| syntax("expected `action = inputs`".to_string()))?;
+            let (name, inputs) = (name.trim(), inputs.trim());
+            if name == "stick-deadzone" {
+                self.stick_deadzone =
+                    inputs.parse().map_err(|_| syntax("expected a number up to 32767".to_string()))?;
+                continue;
+            }
+            if name == "turbo-rate" {
+                self.turbo_rate = match inputs.split_whitespace().map(str::parse).collect::<Vec<_>>()[..] {
+                    [Ok(on), Ok(off)] if on > 0 && off > 0 => (on, off),
+                    _ => return Err(syntax("expected frames held and released, like `2 2`".to_string())),
+                };
+                continue;
+            }
+
+            let action = parse_action(name).ok_or_else(|| syntax(format!("unknown action `{}`", name)))?;
+            self.unbind(action);
+            for input in inputs.split(',').map(str::trim).filter(|input| !input.is_empty()) {
+                match input.split_once(':') {
+                    Some(("key", key)) => {
+                        let (key, shift) = match key.strip_prefix("Shift+") {
+                            Some(key) => (key, true),
+                            None => (key, false),
+                        };
+                        let key = Keycode::from_name(key).ok_or_else(|| syntax(format!("unknown key `{}`", key)))?;
+                        self.keys.insert((key, shift), action);
+                    }
+                    Some(("pad", button)) => {
+                        let button = controller::Button::from_string(button)
+                            .ok_or_else(|| syntax(format!("unknown controller button `{}`", button)))?;
+                        self.pad.insert(button, action);
+                    }
+                    _ => return Err(syntax(format!("`{}` should be key:<name> or pad:<name>", input))),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn unbind(&mut self, action: Action) {
+        self.keys.retain(|_, bound| *bound != action);
+        self.pad.retain(|_, bound| *bound != action);
+    }
+}
+
+fn parse_action(name: &str) -> Option<Action> {
+    Some(match name {
+        "a" => Action::BUTTON(Button::A),
+        "b" => Action::BUTTON(Button::B),
+        "start" => Action::BUTTON(Button::START),
+        "select" => Action::BUTTON(Button::SELECT),
+        "up" => Action::BUTTON(Button::UP),
+        "down" => Action::BUTTON(Button::DOWN),
+        "left" => Action::BUTTON(Button::LEFT),
+        "right" => Action::BUTTON(Button::RIGHT),
+        "turbo-a" => Action::TURBO(Button::A),
+        "turbo-b" => Action::TURBO(Button::B),
+        "quit" => Action::HOTKEY(Hotkey::QUIT),
+        "toggle-background" => Action::HOTKEY(Hotkey::TOGGLEBACKGROUND),
+        "toggle-window" => Action::HOTKEY(Hotkey::TOGGLEWINDOW),
+        "toggle-sprites" => Action::HOTKEY(Hotkey::TOGGLESPRITES),
+        "tint-layers" => Action::HOTKEY(Hotkey::TINTLAYERS),
+        "next-palette" => Action::HOTKEY(Hotkey::NEXTPALETTE),
+        "next-filter" => Action::HOTKEY(Hotkey::NEXTFILTER),
+        "ghosting" => Action::HOTKEY(Hotkey::GHOSTING),
+        "record" => Action::HOTKEY(Hotkey::RECORD),
+        "record-gif" => Action::HOTKEY(Hotkey::RECORDGIF),
+        "stop-movie" => Action::HOTKEY(Hotkey::STOPMOVIE),
+        "screenshot" => Action::HOTKEY(Hotkey::SCREENSHOT),
+        "raw-screenshot" => Action::HOTKEY(Hotkey::RAWSCREENSHOT),
+        _ => return None,
+    })
+}
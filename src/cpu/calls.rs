@@ -0,0 +1,60 @@
+// Shadow of the calls made by the CPU, for the debugger to follow. The real
+// stack is just memory, that games are free to fill with other things or to
+// unwind by hand, so frames are kept on the side: pushed by CALL, RST and
+// interrupt dispatch, and popped by returns. A return pops every frame whose
+// return address is at or below SP, which also gets rid of frames that were
+// left without returning.
+
+// frames past this are dropped, oldest first, for code that never returns
+const MAX_DEPTH: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CallKind {
+    CALL,
+    RST,
+    INTERRUPT,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Frame {
+    pub kind: CallKind,
+    pub target: u16,
+    pub ret: u16, // return address
+    pub sp: u16,  // where the return address was pushed
+}
+
+pub struct CallStack {
+    frames: Vec<Frame>,
+}
+
+impl CallStack {
+    pub fn init() -> Self {
+        Self { frames: vec![] }
+    }
+
+    // to be called once the return address is pushed
+    #[inline(always)]
+    pub fn push(&mut self, kind: CallKind, target: u16, ret: u16, sp: u16) {
+        if self.frames.len() == MAX_DEPTH {
+            self.frames.remove(0);
+        }
+        self.frames.push(Frame { kind, target, ret, sp });
+    }
+
+    // to be called before popping the return address
+    #[inline(always)]
+    pub fn ret(&mut self, sp: u16) {
+        while self.frames.last().is_some_and(|frame| frame.sp <= sp) {
+            self.frames.pop();
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    // outermost first
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+}
@@ -1,4 +1,4 @@
-use crate::cpu::{Reg, C_FLAG, H_FLAG, N_FLAG, Z_FLAG};
+use crate::cpu::{calls::CallKind, Reg, C_FLAG, H_FLAG, N_FLAG, Z_FLAG};
 use crate::gameboy::GameBoy;
 
 // CB prefix
@@ -1052,6 +1052,7 @@ macro_rules! call {
             gb.cycle_write(gb.cpu.sp, ret[1]);
             gb.cpu.sp.dec();
             gb.cycle_write(gb.cpu.sp, ret[0]);
+            gb.calls.push(CallKind::CALL, jp_addr, gb.cpu.pc, gb.cpu.sp);
 
             gb.cpu.pc = jp_addr;
         }
@@ -1076,6 +1077,7 @@ macro_rules! call {
                     gb.cycle_write(gb.cpu.sp, ret[1]);
                     gb.cpu.sp.dec();
                     gb.cycle_write(gb.cpu.sp, ret[0]);
+                    gb.calls.push(CallKind::CALL, jp_addr, gb.cpu.pc, gb.cpu.sp);
 
                     gb.cpu.pc = jp_addr;
                 }
@@ -1151,6 +1153,7 @@ macro_rules! jr {
 
 #[inline(always)]
 fn _ret(gb: &mut GameBoy) {
+    gb.calls.ret(gb.cpu.sp);
     gb.cpu.pc = {
         let lo = gb.cycle_read(gb.cpu.sp) as u16;
         gb.cpu.sp.inc();
@@ -1193,6 +1196,7 @@ macro_rules! rst {
             gb.cycle_write(gb.cpu.sp, addr[1]);
             gb.cpu.sp.dec();
             gb.cycle_write(gb.cpu.sp, addr[0]);
+            gb.calls.push(CallKind::RST, $hx, gb.cpu.pc, gb.cpu.sp);
             gb.cpu.pc = $hx;
         }
     };
@@ -1372,7 +1376,27 @@ fn scf(gb: &mut GameBoy) {
     gb.cpu.f |= C_FLAG;
 }
 
-fn stop(_gb: &mut GameBoy) {}
+// On DMG, what STOP does depends on whether a button is held and an interrupt
+// is pending. It may skip the byte after it, and when it does enter STOP mode
+// it resets DIV, halting the CPU, timer and LCD until a joypad line goes low.
+fn stop(gb: &mut GameBoy) {
+    match (gb.joypad_held(), gb.intr.fetch().is_some()) {
+        (true, true) => {}
+        (true, false) => {
+            gb.cpu.pc.inc();
+            gb.halt = true;
+        }
+        (false, true) => {
+            gb.timer.write_div();
+            gb.stop = true;
+        }
+        (false, false) => {
+            gb.cpu.pc.inc();
+            gb.timer.write_div();
+            gb.stop = true;
+        }
+    }
+}
 
 fn undefined(_gb: &mut GameBoy) {}
 
@@ -1,6 +1,8 @@
 use crate::gameboy::GameBoy;
+use calls::CallKind;
 use instructions::OPCODES;
 
+pub mod calls;
 pub mod instructions;
 
 pub const Z_FLAG: u8 = 0b10000000;
@@ -93,7 +95,16 @@ impl Cpu {
 
 impl GameBoy {
     pub fn cpu_step(&mut self) {
+        // only the joypad and cartridge keep working in STOP mode
+        if self.stop {
+            self.cycle_joypad(4);
+            self.cycle_cart(4);
+            self.ppu.idle(4);
+            return;
+        }
+
         self.advance_cycles(4);
+        self.hooks.pc = self.cpu.pc;
 
         let ime = self.intr.current_ime();
         match self.intr.fetch() {
@@ -119,6 +130,10 @@ impl GameBoy {
 
                     self.cpu.sp.dec();
                     self.cycle_write(self.cpu.sp, addr[0]);
+                    // a cancelled dispatch is a plain jump to $0000, not a handler call
+                    if jp_addr != 0 {
+                        self.calls.push(CallKind::INTERRUPT, jp_addr, self.cpu.pc, self.cpu.sp);
+                    }
 
                     // jump to intr handler addr
                     self.cpu.pc = jp_addr;
@@ -1,5 +1,14 @@
-use crate::gameboy::GameBoy;
-use std::io::Write;
+use crate::{
+    cpu::calls::CallKind,
+    gameboy::GameBoy,
+    mmu::hooks::{Access, AccessKind, Hook},
+};
+use expr::Expr;
+use std::{fmt, io::Write, path::Path};
+use symbols::Symbols;
+
+mod expr;
+mod symbols;
 
 #[derive(Clone)]
 pub enum Arg {
@@ -9,15 +18,37 @@ pub enum Arg {
     None,
 }
 
-struct WatchPoint {
-    // TODO: this does not keep track of what ROM/RAM bank the address belongs to
+// An address, written as `bank:addr` to only refer to it while that ROM or
+// SRAM bank is mapped
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Location {
     addr: u16,
-    last_val: u8,
+    bank: Option<u16>,
+}
+
+struct BreakPoint {
+    loc: Location,
+    cond: Option<(String, Expr)>, // source, to be listed, and parsed condition
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum WatchKind {
+    READ,
+    WRITE,
+    ACCESS,
+}
+
+// Stops on accesses to len bytes starting at loc
+struct WatchPoint {
+    loc: Location,
+    len: u16,
+    kind: WatchKind,
 }
 
 pub struct Debugger {
-    breakpoints: Vec<u16>,
+    breakpoints: Vec<BreakPoint>,
     watchpoints: Vec<WatchPoint>,
+    symbols: Symbols,
     last_cmd: String,
     stdin: std::io::Stdin,
     stdout: std::io::Stdout,
@@ -30,11 +61,22 @@ struct DbgConfig {
 }
 
 impl Debugger {
-    pub fn init() -> Self {
+    // Symbols are loaded from the .sym file next to the ROM, if there is one
+    pub fn init(rom_path: &str) -> Self {
+        let sym_path = Path::new(rom_path).with_extension("sym");
+        let symbols = match sym_path.exists() {
+            true => Symbols::load(&sym_path.to_string_lossy()).unwrap_or_else(|e| {
+                println!("{}", e);
+                Symbols::default()
+            }),
+            false => Symbols::default(),
+        };
+
         Self {
             last_cmd: "help".to_string(),
             breakpoints: vec![],
             watchpoints: vec![],
+            symbols,
             stdin: std::io::stdin(),
             stdout: std::io::stdout(),
             config: DbgConfig { disasm: true, regs: true },
@@ -42,7 +84,10 @@ impl Debugger {
     }
 
     pub fn prompt(&mut self, gb: &mut GameBoy) {
-        print!("> ");
+        match self.describe(gb, gb.cpu.pc) {
+            Some(function) => print!("{} > ", function),
+            None => print!("> "),
+        }
         if !self.stdout.flush().is_ok() {
             println!();
             return;
@@ -65,17 +110,11 @@ impl Debugger {
             self.last_cmd = stripped_input.to_string();
         }
 
-        let splitted_input: Vec<&str> = stripped_input.split_whitespace().collect();
-
-        if splitted_input.len() > 3 {
-            println!("Invalid number of arguments. They range 0-2.");
-            return;
-        }
-
         let cmd_end = stripped_input.find(' ').unwrap_or(stripped_input.len());
         let slash_pos = std::cmp::min(cmd_end, stripped_input.find('/').unwrap_or(stripped_input.len()));
 
-        let cmd_name = &stripped_input[0..slash_pos];
+        let cmd_name = stripped_input[0..slash_pos].to_string();
+        let args = stripped_input[cmd_end..].trim().to_string();
         let mod_str = match stripped_input[slash_pos..cmd_end].to_string().strip_prefix("/") {
             None => stripped_input[slash_pos..cmd_end].to_string(),
             Some(stripped) => stripped.to_string(),
@@ -92,85 +131,154 @@ impl Debugger {
             }
         }
 
+        if let Err(e) = self.run_cmd(gb, &cmd_name, modif, &args) {
+            println!("{}", e);
+            return;
+        }
+
+        if self.config.regs {
+            self.regs_cmd(gb);
+        }
+
+        if self.config.disasm {
+            self.disasm_cmd(gb, None, gb.cpu.pc);
+        }
+    }
+
+    fn run_cmd(&mut self, gb: &mut GameBoy, cmd_name: &str, modif: Option<u16>, args: &str) -> Result<(), String> {
+        // these take expressions, which may contain spaces
+        match cmd_name {
+            "b" | "break" if !args.is_empty() => return self.breakpoint_cmd(gb, args),
+            "x" | "examine" if !args.is_empty() => {
+                let addr = Expr::parse(args, &self.symbols)?.eval(gb)? as u16;
+                self.examine_cmd(gb, modif, addr);
+                return Ok(());
+            }
+            "p" | "print" if !args.is_empty() => return self.print_cmd(gb, args),
+            "de" | "delete" if !args.is_empty() => {
+                self.delete_cmd(Location::parse(gb, args, &self.symbols)?);
+                return Ok(());
+            }
+            "w" | "watch" | "rw" | "rwatch" | "aw" | "awatch" if !args.is_empty() => {
+                let kind = match cmd_name {
+                    "rw" | "rwatch" => WatchKind::READ,
+                    "aw" | "awatch" => WatchKind::ACCESS,
+                    _ => WatchKind::WRITE,
+                };
+                self.watchpoint_cmd(Location::parse(gb, args, &self.symbols)?, modif.unwrap_or(1).max(1), kind);
+                return Ok(());
+            }
+            "dw" | "delwatch" if !args.is_empty() => {
+                self.delwatch_cmd(Location::parse(gb, args, &self.symbols)?);
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let splitted_args: Vec<&str> = args.split_whitespace().collect();
+        if splitted_args.len() > 2 {
+            return Err("Invalid number of arguments. They range 0-2.".to_string());
+        }
+
         let mut arg1 = Arg::None;
         let mut arg2 = Arg::None;
-        if splitted_input.len() > 1 {
-            arg1 = match self.eval_arg(gb, splitted_input[1]) {
-                Ok(arg) => arg,
-                Err(e) => {
-                    println!("{}", e);
-                    return;
-                }
-            }
+        if !splitted_args.is_empty() {
+            arg1 = self.eval_arg(gb, splitted_args[0])?;
         }
-        if splitted_input.len() > 2 {
-            arg2 = match self.eval_arg(gb, splitted_input[2]) {
-                Ok(arg) => arg,
-                Err(e) => {
-                    println!("{}", e);
-                    return;
-                }
-            }
+        if splitted_args.len() > 1 {
+            arg2 = self.eval_arg(gb, splitted_args[1])?;
         }
 
         match (cmd_name, modif, arg1, arg2) {
             ("c" | "continue", None, Arg::None, Arg::None) => self.continue_cmd(gb),
             ("s" | "step", _, Arg::None, Arg::None) => self.step_cmd(gb, modif),
             ("n" | "next", _, Arg::None, Arg::None) => self.next_cmd(gb, modif),
+            ("f" | "finish", None, Arg::None, Arg::None) => self.finish_cmd(gb),
+            ("bt" | "backtrace", None, Arg::None, Arg::None) => self.backtrace_cmd(gb),
             ("h" | "help", None, Arg::None, Arg::None) => self.help_cmd("".to_string()),
             ("h" | "help", None, Arg::Str(cmd_name), Arg::None) => self.help_cmd(cmd_name),
-            ("b" | "break", None, Arg::Numeric(addr), Arg::None) => self.breakpoint_cmd(addr),
-            ("de" | "delete", None, Arg::Numeric(addr), Arg::None) => self.delete_cmd(addr),
-            ("w" | "watch", None, Arg::Numeric(addr), Arg::None) => self.watchpoint_cmd(gb, addr),
-            ("dw" | "delwatch", None, Arg::Numeric(addr), Arg::None) => self.delwatch_cmd(addr),
             ("l" | "list", None, Arg::None, Arg::None) => self.list_cmd(),
             ("d" | "disassemble", _, Arg::None, Arg::None) => self.disasm_cmd(gb, modif, gb.cpu.pc),
             ("d" | "disassemble", _, Arg::Numeric(addr), Arg::None) => self.disasm_cmd(gb, modif, addr),
-            ("x" | "examine", _, Arg::Numeric(addr), Arg::None) => self.examine_cmd(gb, modif, addr),
             ("r" | "regs" | "registers", None, Arg::None, Arg::None) => self.regs_cmd(gb),
             ("set", _, Arg::Str(config), Arg::Bool(state)) => self.set_cmd(config, state),
             ("cl" | "clear", None, Arg::None, Arg::None) => self.clear_cmd(),
             _ => self.help_cmd(cmd_name.to_string()),
         };
-
-        if self.config.regs {
-            self.regs_cmd(gb);
-        }
-
-        if self.config.disasm {
-            self.disasm_cmd(gb, None, gb.cpu.pc);
-        }
+        Ok(())
     }
 
     fn continue_cmd(&mut self, gb: &mut GameBoy) {
-        for i in 0..self.watchpoints.len() {
-            self.watchpoints[i].last_val = gb.pure_read(self.watchpoints[i].addr);
-        }
+        self.run(gb, |_| false);
+    }
 
+    // Steps until done is true, or a breakpoint or watchpoint is hit
+    fn run(&mut self, gb: &mut GameBoy, done: impl Fn(&GameBoy) -> bool) {
+        gb.hooks.set(self.watchpoints.iter().map(|wp| wp.hook()).collect());
         loop {
             gb.cpu_step();
+            if self.watchpoint_hit(gb) || self.breakpoint_hit(gb) || done(gb) {
+                break;
+            }
+        }
+        gb.hooks.set(vec![]);
+    }
 
-            let mut changes = vec![];
-            for i in 0..self.watchpoints.len() {
-                let val = gb.pure_read(self.watchpoints[i].addr);
-                if val != self.watchpoints[i].last_val {
-                    changes.push((i + 1, val, self.watchpoints[i].addr))
+    // Reports every access made to a watchpoint during the last step
+    fn watchpoint_hit(&self, gb: &GameBoy) -> bool {
+        let mut hit = false;
+        for access in gb.hooks.take() {
+            for (i, wp) in self.watchpoints.iter().enumerate() {
+                if !wp.matches(gb, &access) {
+                    continue;
                 }
-            }
-            if !changes.is_empty() {
-                for i in 0..changes.len() {
-                    println!(
-                        "Watchpoint {}: value ${:02X} written to address ${:04X}",
-                        changes[i].0, changes[i].1, changes[i].2
-                    );
+                hit = true;
+                match access.kind {
+                    AccessKind::READ => println!(
+                        "Watchpoint {}: value ${:02X} read from address ${:04X} at ${:04X}",
+                        i + 1,
+                        access.val,
+                        access.addr,
+                        access.pc
+                    ),
+                    AccessKind::WRITE => println!(
+                        "Watchpoint {}: value ${:02X} written to address ${:04X} at ${:04X}",
+                        i + 1,
+                        access.val,
+                        access.addr,
+                        access.pc
+                    ),
                 }
-                break;
             }
+        }
+        hit
+    }
 
-            if self.breakpoints.binary_search(&gb.cpu.pc).is_ok() {
+    // Whether a breakpoint at PC, in the mapped bank, has its condition met
+    fn breakpoint_hit(&self, gb: &GameBoy) -> bool {
+        let pc = gb.cpu.pc;
+        let start = self.breakpoints.partition_point(|bp| bp.loc.addr < pc);
+        for (i, bp) in self.breakpoints.iter().enumerate().skip(start) {
+            if bp.loc.addr != pc {
                 break;
             }
+            if !bp.loc.mapped(gb) {
+                continue;
+            }
+
+            match &bp.cond {
+                None => return true,
+                Some((src, cond)) => match cond.eval(gb) {
+                    Ok(0) => {}
+                    Ok(_) => return true,
+                    Err(e) => {
+                        println!("Breakpoint {} condition {}: {}", i + 1, src, e);
+                        return true;
+                    }
+                },
+            }
         }
+        false
     }
 
     fn step_cmd(&mut self, gb: &mut GameBoy, modif: Option<u16>) {
@@ -183,25 +291,46 @@ impl Debugger {
         }
     }
 
+    // Calls, and interrupts fired in between, run until they return
     fn next_cmd(&mut self, gb: &mut GameBoy, modif: Option<u16>) {
         let steps = match modif {
             None => 1,
             Some(n) => n,
         };
         for _ in 0..steps {
-            let mut count = 0;
-            loop {
-                match gb.dpc(0) {
-                    0xC4 | 0xCC | 0xD4 | 0xDC | 0xCD => count += 1,
-                    0xC0 | 0xC8 | 0xC9 | 0xD0 | 0xD8 | 0xD9 => count -= 1,
-                    _ => {}
-                }
-                gb.cpu_step();
-                if count == 0 {
-                    break;
-                }
-            }
+            let depth = gb.calls.depth();
+            self.run(gb, |gb| gb.calls.depth() <= depth);
+        }
+    }
+
+    // Runs until the current function returns, unless it was called by the
+    // top-level code
+    fn finish_cmd(&mut self, gb: &mut GameBoy) {
+        let depth = gb.calls.depth();
+        if depth == 0 {
+            println!("Not in a function");
+            return;
         }
+        self.run(gb, |gb| gb.calls.depth() < depth);
+    }
+
+    fn backtrace_cmd(&mut self, gb: &mut GameBoy) {
+        let location = |addr| match self.describe(gb, addr) {
+            Some(function) => format!("${:04X} in {}", addr, function),
+            None => format!("${:04X}", addr),
+        };
+
+        // frames are shown by where execution goes back to
+        println!("#0  {}", location(gb.cpu.pc));
+        for (i, frame) in gb.calls.frames().iter().rev().enumerate() {
+            let kind = match frame.kind {
+                CallKind::CALL => "".to_string(),
+                CallKind::RST => format!(" (RST ${:02X})", frame.target),
+                CallKind::INTERRUPT => format!(" (interrupted by ${:02X})", frame.target),
+            };
+            println!("#{:<2} {}{}", i + 1, location(frame.ret), kind);
+        }
+        println!();
     }
 
     fn examine_cmd(&mut self, gb: &mut GameBoy, modif: Option<u16>, addr: u16) {
@@ -227,50 +356,74 @@ impl Debugger {
         println!();
     }
 
-    fn breakpoint_cmd(&mut self, addr: u16) {
-        match self.breakpoints.binary_search(&addr) {
-            Ok(_) => {
-                println!("Breakpoint already at ${:04X}", addr);
+    fn print_cmd(&mut self, gb: &mut GameBoy, expr: &str) -> Result<(), String> {
+        let val = Expr::parse(expr, &self.symbols)?.eval(gb)?;
+        println!("{} = ${:04X} ({})", expr, val as u16, val);
+        println!();
+        Ok(())
+    }
+
+    // args is a location, optionally followed by `if condition`. Setting a
+    // breakpoint where there already is one replaces its condition.
+    fn breakpoint_cmd(&mut self, gb: &mut GameBoy, args: &str) -> Result<(), String> {
+        let (loc, cond) = match args.split_once(" if ") {
+            Some((loc, cond)) => (loc, Some(cond.trim())),
+            None => (args, None),
+        };
+        let loc = Location::parse(gb, loc, &self.symbols)?;
+        let cond = match cond {
+            Some(src) => Some((src.to_string(), Expr::parse(src, &self.symbols)?)),
+            None => None,
+        };
+
+        match self.breakpoints.binary_search_by(|bp| bp.loc.cmp(&loc)) {
+            Ok(pos) => {
+                self.breakpoints[pos].cond = cond;
+                println!("Breakpoint at {} updated", loc);
             }
             Err(pos) => {
-                self.breakpoints.insert(pos, addr);
-                println!("Breakpoint set at ${:04X}", addr);
+                self.breakpoints.insert(pos, BreakPoint { loc, cond });
+                println!("Breakpoint set at {}", loc);
             }
         }
+        Ok(())
     }
 
-    fn delete_cmd(&mut self, addr: u16) {
-        match self.breakpoints.binary_search(&addr) {
+    fn delete_cmd(&mut self, loc: Location) {
+        match self.breakpoints.binary_search_by(|bp| bp.loc.cmp(&loc)) {
             Ok(pos) => {
                 _ = self.breakpoints.remove(pos);
-                println!("Deleted breakpoint at ${:04X}", addr);
+                println!("Deleted breakpoint at {}", loc);
             }
             Err(_) => {
-                println!("No breakpoint at ${:04X}", addr);
+                println!("No breakpoint at {}", loc);
             }
         }
     }
 
-    fn watchpoint_cmd(&mut self, gb: &mut GameBoy, addr: u16) {
-        match self.watchpoints.binary_search_by(|wp| wp.addr.cmp(&addr)) {
-            Ok(_) => {
-                println!("Watchpoint already at ${:04X}", addr);
+    // Setting a watchpoint where there already is one replaces it
+    fn watchpoint_cmd(&mut self, loc: Location, len: u16, kind: WatchKind) {
+        let wp = WatchPoint { loc, len, kind };
+        match self.watchpoints.binary_search_by(|wp| wp.loc.cmp(&loc)) {
+            Ok(pos) => {
+                self.watchpoints[pos] = wp;
+                println!("Watchpoint at {} updated", loc);
             }
             Err(pos) => {
-                self.watchpoints.insert(pos, WatchPoint { addr, last_val: gb.pure_read(addr) });
-                println!("Watchpoint set at ${:04X}", addr);
+                self.watchpoints.insert(pos, wp);
+                println!("Watchpoint set at {}", loc);
             }
         }
     }
 
-    fn delwatch_cmd(&mut self, addr: u16) {
-        match self.watchpoints.binary_search_by(|wp| wp.addr.cmp(&addr)) {
+    fn delwatch_cmd(&mut self, loc: Location) {
+        match self.watchpoints.binary_search_by(|wp| wp.loc.cmp(&loc)) {
             Ok(pos) => {
                 _ = self.watchpoints.remove(pos);
-                println!("Deleted watchpoint at ${:04X}", addr);
+                println!("Deleted watchpoint at {}", loc);
             }
             Err(_) => {
-                println!("No breakpoint at ${:04X}", addr);
+                println!("No watchpoint at {}", loc);
             }
         }
     }
@@ -278,13 +431,17 @@ impl Debugger {
     fn list_cmd(&mut self) {
         println!("BREAKPOINTS");
         for i in 0..self.breakpoints.len() {
-            println!("Breakpoint {}: ${:X}", i + 1, self.breakpoints[i]);
+            match &self.breakpoints[i].cond {
+                None => println!("Breakpoint {}: {}", i + 1, self.breakpoints[i].loc),
+                Some((src, _)) => println!("Breakpoint {}: {} if {}", i + 1, self.breakpoints[i].loc, src),
+            }
         }
         println!();
 
         println!("WATCHPOINTS");
         for i in 0..self.watchpoints.len() {
-            println!("Watchpoint {}: ${:X}", i + 1, self.watchpoints[i].addr);
+            let wp = &self.watchpoints[i];
+            println!("Watchpoint {}: {} ({} byte(s), {})", i + 1, wp.loc, wp.len, wp.kind.name());
         }
         println!();
     }
@@ -304,6 +461,9 @@ impl Debugger {
         };
 
         for _ in 1..=count {
+            if let Some(label) = self.symbols.label(gb, addr) {
+                println!("{}:", label);
+            }
             let (dis, len) = self.disassemble(gb, addr);
             let mut padding = "    ";
             if addr == gb.cpu.pc {
@@ -322,12 +482,17 @@ impl Debugger {
                 println!("{}c{}ontinue -- continues execution without stopping", ULINE, RESET);
                 println!("{}s{}tep -- executes the next instruction, stepping into function calls", ULINE, RESET);
                 println!("{}n{}ext -- executes the next instruction, stepping over function calls", ULINE, RESET);
+                println!("{}f{}inish -- continues until the current function returns", ULINE, RESET);
+                println!("{}b{}ack{}t{}race -- displays the functions called to get here", ULINE, RESET, ULINE, RESET);
                 println!("e{}x{}amine -- displays a range of values from memory", ULINE, RESET);
+                println!("{}p{}rint -- displays the value of an expression", ULINE, RESET);
                 println!("{}r{}egisters -- displays value of cpu registers", ULINE, RESET);
                 println!("{}d{}isassemble -- disassembles instructions at PC or at a specified address", ULINE, RESET);
                 println!("{}b{}reak -- creates a breakpoint at a specified address", ULINE, RESET);
                 println!("{}de{}lete -- deletes a breakpoint at a specified address", ULINE, RESET);
                 println!("{}w{}atch -- creates a watchpoint at a specified address", ULINE, RESET);
+                println!("{}rw{}atch -- creates a read watchpoint at a specified address", ULINE, RESET);
+                println!("{}aw{}atch -- creates an access watchpoint at a specified address", ULINE, RESET);
                 println!("{}d{}el{}w{}atch -- deletes a watchpoint at a specified address", ULINE, RESET, ULINE, RESET);
                 println!("{}l{}ist -- lists live breakpoints and watchpoints", ULINE, RESET);
                 println!("{}s{}et -- sets a configuration flag", ULINE, RESET);
@@ -350,13 +515,36 @@ impl Debugger {
                 println!();
             }
             "n" | "next" => {
-                println!("{}s{}tep[/count] -- executes [count] next instruction(s)", ULINE, RESET);
-                println!("usage: step");
+                println!("{}n{}ext[/count] -- executes [count] next instruction(s)", ULINE, RESET);
+                println!("             calls and interrupts run until they return");
+                println!("usage: next");
+                println!();
+            }
+            "f" | "finish" => {
+                println!("{}f{}inish -- continues until the current function returns", ULINE, RESET);
+                println!("             interrupts firing in between don't count as returns");
+                println!("usage: finish");
+                println!();
+            }
+            "bt" | "backtrace" => {
+                println!("{}b{}ack{}t{}race -- displays the functions called to get here", ULINE, RESET, ULINE, RESET);
+                println!("             calls, RSTs and interrupts are followed as they happen");
+                println!("usage: backtrace");
                 println!();
             }
             "x" | "examine" => {
                 println!("e{}x{}amine -- displays a range of values from memory", ULINE, RESET);
-                println!("usage: examine[/count] address");
+                println!("usage: examine[/count] expression");
+                println!();
+            }
+            "p" | "print" => {
+                println!("{}p{}rint -- displays the value of an expression", ULINE, RESET);
+                println!("usage: print expression\n");
+                println!("Expressions can use numbers ($hex, %binary or decimal), registers (a, f, b, c, d, e,");
+                println!("h, l, af, bc, de, hl, sp, pc), flags (zf, nf, hf, cf), symbols from the ROM's .sym file");
+                println!("and bytes in memory ([address]).");
+                println!("Operators are the same as in C: ! ~ - * / % + - << >> < <= > >= == != & ^ | && ||");
+                println!("example: print [hl] + 1");
                 println!();
             }
             "r" | "regs" | "registers" => {
@@ -366,29 +554,47 @@ impl Debugger {
             }
             "d" | "disassemble" => {
                 println!("{}d{}isassemble -- disassembles instructions at a specified address", ULINE, RESET);
+                println!("             labels from the ROM's .sym file are shown, and used for jump targets");
                 println!("usage: disassemble[/count] address");
                 println!();
             }
             "b" | "break" => {
                 println!("{}b{}reak -- creates a breakpoint at a specified address", ULINE, RESET);
                 println!("             program execution will stop when arriving at a breakpoint");
-                println!("usage: break address");
+                println!("             if a condition is given, only when it is true (see help print)");
+                println!("             if a bank is given, only when that ROM or SRAM bank is mapped");
+                println!("usage: break [bank:]address [if condition]");
+                println!("example: break $1234 if a == $10 && [$C000] > 3");
+                println!("         break Main.loop if [wLives] == 0");
                 println!();
             }
             "de" | "delete" => {
                 println!("{}de{}lete -- deletes a breakpoint at a specified address", ULINE, RESET);
-                println!("usage: delete address");
+                println!("usage: delete [bank:]address");
                 println!();
             }
             "w" | "watch" => {
-                println!("{}w{}atch -- creates a watchpoint at a specified address", ULINE, RESET);
-                println!("             program execution will stop when value at a watchpoint changes");
-                println!("usage: watch address");
+                println!("{}w{}atch[/length] -- creates a watchpoint at a specified address", ULINE, RESET);
+                println!("             program execution will stop when a watched byte is written to");
+                println!("             if a bank is given, only while that ROM or SRAM bank is mapped");
+                println!("usage: watch[/length] [bank:]address");
+                println!();
+            }
+            "rw" | "rwatch" => {
+                println!("{}rw{}atch[/length] -- creates a read watchpoint at a specified address", ULINE, RESET);
+                println!("             program execution will stop when a watched byte is read");
+                println!("usage: rwatch[/length] [bank:]address");
+                println!();
+            }
+            "aw" | "awatch" => {
+                println!("{}aw{}atch[/length] -- creates an access watchpoint at a specified address", ULINE, RESET);
+                println!("             program execution will stop when a watched byte is read or written to");
+                println!("usage: awatch[/length] [bank:]address");
                 println!();
             }
             "dw" | "delwatch" => {
                 println!("{}de{}l{}w{}atch -- deletes a watchpoint at a specified address", ULINE, RESET, ULINE, RESET);
-                println!("usage: delwatch address");
+                println!("usage: delwatch [bank:]address");
                 println!();
             }
             "l" | "list" => {
@@ -428,8 +634,9 @@ impl Debugger {
             "off" => {
                 return Ok(Arg::Bool(false));
             }
-            "help" | "continue" | "step" | "disassemble" | "break" | "delete" | "watch" | "delwatch" | "list"
-            | "examine" | "registers" | "set" | "clear" => {
+            "help" | "continue" | "step" | "next" | "finish" | "backtrace" | "disassemble" | "break" | "delete"
+            | "watch" | "rwatch" | "awatch" | "delwatch" | "list" | "examine" | "print" | "registers" | "set"
+            | "clear" => {
                 return Ok(Arg::Str(arg_str.to_string()));
             }
             "disasm" | "regs" => {
@@ -460,6 +667,14 @@ impl Debugger {
         arg
     }
 
+    // function+offset, for the function addr is in if the symbols know it
+    fn describe(&self, gb: &GameBoy, addr: u16) -> Option<String> {
+        match self.symbols.function(gb, addr)? {
+            (name, 0) => Some(name.to_string()),
+            (name, offset) => Some(format!("{}+${:X}", name, offset)),
+        }
+    }
+
     pub fn disassemble(&self, gb: &mut GameBoy, addr: u16) -> (String, u8) {
         let opcode = gb.pure_read(addr);
         let mut mnemonic = OPCODES_STR[opcode as usize].to_string();
@@ -478,13 +693,23 @@ impl Debugger {
         if mnemonic.contains("U16") {
             let param1 = gb.pure_read(u16::wrapping_add(addr, 1));
             let param2 = gb.pure_read(u16::wrapping_add(addr, 2));
-            mnemonic = mnemonic.replace("U16", &format!("${:04X}", (((param2 as u16) << 8) + param1 as u16)));
+            let target = ((param2 as u16) << 8) + param1 as u16;
+            let operand = match self.symbols.label(gb, target) {
+                Some(label) if mnemonic.starts_with("JP") || mnemonic.starts_with("CALL") => label.to_string(),
+                _ => format!("${:04X}", target),
+            };
+            mnemonic = mnemonic.replace("U16", &operand);
             return (mnemonic, 3);
         }
 
         if mnemonic.contains("I8") {
             let param = gb.pure_read(u16::wrapping_add(addr, 1));
-            mnemonic = mnemonic.replace("I8", &format!("${:02X}", param));
+            let target = u16::wrapping_add(addr, 2).wrapping_add(param as i8 as u16);
+            let operand = match self.symbols.label(gb, target) {
+                Some(label) if mnemonic.starts_with("JR") => label.to_string(),
+                _ => format!("${:02X}", param),
+            };
+            mnemonic = mnemonic.replace("I8", &operand);
             mnemonic += " (";
             if param as i8 > 0 {
                 mnemonic += "+";
@@ -497,6 +722,67 @@ impl Debugger {
     }
 }
 
+impl Location {
+    // Both bank and address are expressions. A symbol on its own also gives
+    // the bank it is in.
+    fn parse(gb: &GameBoy, src: &str, symbols: &Symbols) -> Result<Self, String> {
+        if let Some(loc) = symbols.location(src.trim()) {
+            return Ok(loc);
+        }
+
+        let (bank, addr) = match src.split_once(':') {
+            Some((bank, addr)) => (Some(Expr::parse(bank, symbols)?.eval(gb)? as u16), addr),
+            None => (None, src),
+        };
+        let addr = Expr::parse(addr, symbols)?.eval(gb)? as u16;
+        if bank.is_some() && gb.mapped_bank(addr).is_none() {
+            return Err(format!("${:04X} is not banked", addr));
+        }
+        Ok(Self { addr, bank })
+    }
+
+    fn mapped(&self, gb: &GameBoy) -> bool {
+        self.bank.is_none() || gb.mapped_bank(self.addr) == self.bank
+    }
+}
+
+impl WatchKind {
+    fn name(&self) -> &str {
+        match self {
+            WatchKind::READ => "read",
+            WatchKind::WRITE => "write",
+            WatchKind::ACCESS => "access",
+        }
+    }
+}
+
+impl WatchPoint {
+    fn hook(&self) -> Hook {
+        Hook {
+            range: self.loc.addr..=self.loc.addr.saturating_add(self.len - 1),
+            read: self.kind != WatchKind::WRITE,
+            write: self.kind != WatchKind::READ,
+        }
+    }
+
+    fn matches(&self, gb: &GameBoy, access: &Access) -> bool {
+        let kind = match access.kind {
+            AccessKind::READ => self.kind != WatchKind::WRITE,
+            AccessKind::WRITE => self.kind != WatchKind::READ,
+        };
+        kind && self.hook().range.contains(&access.addr) && self.loc.mapped(gb)
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.bank {
+            Some(bank) => write!(f, "${:02X}:${:04X}", bank, self.addr),
+            None => write!(f, "${:04X}", self.addr),
+        }
+    }
+}
+
 fn eval_modif(mod_str: String) -> Result<Option<u16>, String> {
     if mod_str == "" {
         return Ok(None);
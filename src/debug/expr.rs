@@ -0,0 +1,320 @@
+// Expressions for the debugger, as in `break $1234 if a == $10 && [$C000] > 3`.
+//
+// Values are numbers, written in decimal, $hex or %binary, registers (a, f, b,
+// c, d, e, h, l, af, bc, de, hl, sp, pc), flags (zf, nf, hf, cf), addresses of
+// symbols and bytes in memory, read with [addr]. Operators work like in C, with the same
+// precedence: ! ~ - (unary), * / %, + -, << >>, < <= > >=, == !=, &, ^, |, &&
+// and ||. Comparisons and logic give 1 or 0, and anything non zero is true.
+use super::symbols::Symbols;
+use crate::{
+    cpu::{C_FLAG, H_FLAG, N_FLAG, Z_FLAG},
+    gameboy::GameBoy,
+};
+
+#[derive(Clone, Debug)]
+pub enum Expr {
+    Num(i64),
+    Reg(Reg),
+    Mem(Box<Expr>),
+    Unary(char, Box<Expr>),
+    Binary(Box<Expr>, &'static str, Box<Expr>),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Reg {
+    A,
+    F,
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    AF,
+    BC,
+    DE,
+    HL,
+    SP,
+    PC,
+    ZF,
+    NF,
+    HF,
+    CF,
+}
+
+// from lowest to highest precedence
+const BINARY_OPS: [&[&str]; 10] = [
+    &["||"],
+    &["&&"],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["==", "!="],
+    &["<=", ">=", "<", ">"],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+impl Expr {
+    // symbols are replaced by their address right away
+    pub fn parse(src: &str, symbols: &Symbols) -> Result<Self, String> {
+        let tokens = tokenize(src)?;
+        let mut parser = Parser { tokens, pos: 0, symbols };
+        let expr = parser.binary(0)?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(token) => Err(format!("Unexpected `{}` in expression", token)),
+        }
+    }
+
+    pub fn eval(&self, gb: &GameBoy) -> Result<i64, String> {
+        Ok(match self {
+            Expr::Num(n) => *n,
+            Expr::Reg(reg) => reg.read(gb) as i64,
+            Expr::Mem(addr) => gb.pure_read(addr.eval(gb)? as u16) as i64,
+            Expr::Unary(op, val) => {
+                let val = val.eval(gb)?;
+                match op {
+                    '-' => val.wrapping_neg(),
+                    '!' => (val == 0) as i64,
+                    '~' => !val & 0xFFFF,
+                    _ => unreachable!(),
+                }
+            }
+            Expr::Binary(lhs, "&&", rhs) => (lhs.eval(gb)? != 0 && rhs.eval(gb)? != 0) as i64,
+            Expr::Binary(lhs, "||", rhs) => (lhs.eval(gb)? != 0 || rhs.eval(gb)? != 0) as i64,
+            Expr::Binary(lhs, op, rhs) => {
+                let (lhs, rhs) = (lhs.eval(gb)?, rhs.eval(gb)?);
+                match *op {
+                    "|" => lhs | rhs,
+                    "^" => lhs ^ rhs,
+                    "&" => lhs & rhs,
+                    "==" => (lhs == rhs) as i64,
+                    "!=" => (lhs != rhs) as i64,
+                    "<=" => (lhs <= rhs) as i64,
+                    ">=" => (lhs >= rhs) as i64,
+                    "<" => (lhs < rhs) as i64,
+                    ">" => (lhs > rhs) as i64,
+                    "<<" => lhs.wrapping_shl(rhs as u32),
+                    ">>" => lhs.wrapping_shr(rhs as u32),
+                    "+" => lhs.wrapping_add(rhs),
+                    "-" => lhs.wrapping_sub(rhs),
+                    "*" => lhs.wrapping_mul(rhs),
+                    "/" | "%" if rhs == 0 => return Err("Division by zero".to_string()),
+                    "/" => lhs.wrapping_div(rhs),
+                    "%" => lhs.wrapping_rem(rhs),
+                    _ => unreachable!(),
+                }
+            }
+        })
+    }
+}
+
+impl Reg {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "a" => Reg::A,
+            "f" => Reg::F,
+            "b" => Reg::B,
+            "c" => Reg::C,
+            "d" => Reg::D,
+            "e" => Reg::E,
+            "h" => Reg::H,
+            "l" => Reg::L,
+            "af" => Reg::AF,
+            "bc" => Reg::BC,
+            "de" => Reg::DE,
+            "hl" => Reg::HL,
+            "sp" => Reg::SP,
+            "pc" => Reg::PC,
+            "zf" => Reg::ZF,
+            "nf" => Reg::NF,
+            "hf" => Reg::HF,
+            "cf" => Reg::CF,
+            _ => return None,
+        })
+    }
+
+    fn read(self, gb: &GameBoy) -> u16 {
+        let cpu = &gb.cpu;
+        match self {
+            Reg::A => cpu.a as u16,
+            Reg::F => cpu.f as u16,
+            Reg::B => cpu.b as u16,
+            Reg::C => cpu.c as u16,
+            Reg::D => cpu.d as u16,
+            Reg::E => cpu.e as u16,
+            Reg::H => cpu.h as u16,
+            Reg::L => cpu.l as u16,
+            Reg::AF => cpu.rd_af(),
+            Reg::BC => cpu.rd_bc(),
+            Reg::DE => cpu.rd_de(),
+            Reg::HL => cpu.rd_hl(),
+            Reg::SP => cpu.sp,
+            Reg::PC => cpu.pc,
+            Reg::ZF => (cpu.f & Z_FLAG != 0) as u16,
+            Reg::NF => (cpu.f & N_FLAG != 0) as u16,
+            Reg::HF => (cpu.f & H_FLAG != 0) as u16,
+            Reg::CF => (cpu.f & C_FLAG != 0) as u16,
+        }
+    }
+}
+
+fn tokenize(src: &str) -> Result<Vec<String>, String> {
+    let mut tokens = vec![];
+    let chars: Vec<char> = src.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        // % starts a binary number where a value is expected, it's modulo otherwise
+        let after_value = tokens
+            .last()
+            .is_some_and(|token: &String| token.ends_with(|c: char| c.is_ascii_alphanumeric() || "_.@#)]".contains(c)));
+        let start = i;
+        if c.is_ascii_alphanumeric() || c == '_' || c == '$' || c == '%' && !after_value {
+            i += 1;
+            // the characters RGBDS allows in labels
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || "_.@#".contains(chars[i])) {
+                i += 1;
+            }
+        } else {
+            let two: String = chars[i..usize::min(i + 2, chars.len())].iter().collect();
+            i += match two.as_str() {
+                "&&" | "||" | "==" | "!=" | "<=" | ">=" | "<<" | ">>" => 2,
+                _ if "+-*/%&|^!~<>()[]".contains(c) => 1,
+                _ => return Err(format!("Unexpected `{}` in expression", c)),
+            };
+        }
+        tokens.push(chars[start..i].iter().collect());
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<String>,
+    pos: usize,
+    symbols: &'a Symbols,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(|token| token.as_str())
+    }
+
+    fn next(&mut self) -> Result<String, String> {
+        let token = self.tokens.get(self.pos).cloned().ok_or_else(|| "Unexpected end of expression".to_string())?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        match self.next()? {
+            next if next == token => Ok(()),
+            next => Err(format!("Expected `{}` but found `{}`", token, next)),
+        }
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, String> {
+        if level == BINARY_OPS.len() {
+            return self.unary();
+        }
+
+        let mut lhs = self.binary(level + 1)?;
+        while let Some(op) = self.peek().and_then(|token| BINARY_OPS[level].iter().find(|op| **op == token)) {
+            self.pos += 1;
+            let rhs = self.binary(level + 1)?;
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        let token = self.next()?;
+        match token.as_str() {
+            "-" | "!" | "~" => Ok(Expr::Unary(token.chars().next().unwrap(), Box::new(self.unary()?))),
+            "(" => {
+                let expr = self.binary(0)?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            "[" => {
+                let addr = self.binary(0)?;
+                self.expect("]")?;
+                Ok(Expr::Mem(Box::new(addr)))
+            }
+            _ => parse_value(&token, self.symbols),
+        }
+    }
+}
+
+fn parse_value(token: &str, symbols: &Symbols) -> Result<Expr, String> {
+    if let Some(reg) = Reg::parse(&token.to_lowercase()) {
+        return Ok(Expr::Reg(reg));
+    }
+    if let Some(loc) = symbols.location(token) {
+        return Ok(Expr::Num(loc.addr as i64));
+    }
+
+    let num = match token.chars().next() {
+        Some('$') => i64::from_str_radix(&token[1..], 16),
+        Some('%') => i64::from_str_radix(&token[1..], 2),
+        _ => token.parse::<i64>(),
+    };
+    num.map(Expr::Num).map_err(|_| format!("Invalid value in expression: {}", token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::load_program;
+
+    fn eval(gb: &GameBoy, src: &str) -> Result<i64, String> {
+        Expr::parse(src, &Symbols::default())?.eval(gb)
+    }
+
+    #[test]
+    fn precedence() {
+        let mut gb = load_program("expr_precedence", &[]);
+        assert_eq!(eval(&gb, "1 + 2 * 3"), Ok(7));
+        assert_eq!(eval(&gb, "(1 + 2) * 3"), Ok(9));
+        assert_eq!(eval(&gb, "1 | 2 == 2"), Ok(1));
+        assert_eq!(eval(&gb, "-2 * -3"), Ok(6));
+
+        gb.cpu.a = 0x10;
+        gb.write(0xC000, 5);
+        assert_eq!(eval(&gb, "a == $10 && [$C000] > 3"), Ok(1));
+        gb.write(0xC000, 3);
+        assert_eq!(eval(&gb, "a == $10 && [$C000] > 3"), Ok(0));
+    }
+
+    #[test]
+    fn binary_or_modulo() {
+        let gb = load_program("expr_binary_or_modulo", &[]);
+        assert_eq!(eval(&gb, "%101"), Ok(5));
+        assert_eq!(eval(&gb, "7 % 4"), Ok(3));
+        assert_eq!(eval(&gb, "7 %101"), Ok(7));
+        assert_eq!(eval(&gb, "%111 % %10"), Ok(1));
+        assert_eq!(eval(&gb, "(%11) % 2"), Ok(1));
+    }
+
+    #[test]
+    fn division_by_zero() {
+        let gb = load_program("expr_division_by_zero", &[]);
+        assert!(eval(&gb, "1 / 0").is_err());
+        assert!(eval(&gb, "1 % (2 - 2)").is_err());
+    }
+
+    #[test]
+    fn unbalanced_brackets() {
+        let gb = load_program("expr_unbalanced_brackets", &[]);
+        for src in ["(1 + 2", "1 + 2)", "[$C000", "$C000]", "[($C000]", "()"] {
+            assert!(eval(&gb, src).is_err(), "`{}` should not parse", src);
+        }
+    }
+}
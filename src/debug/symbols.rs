@@ -0,0 +1,171 @@
+// Labels from the .sym files written by RGBDS (`rgblink -n`), one per line as
+// `bank:addr name`, with ; starting comments. Local labels are written in full,
+// like `Main.loop`, and are never taken as the start of a function. Lines
+// that don't follow that format are skipped.
+use super::Location;
+use crate::gameboy::GameBoy;
+use snafu::Snafu;
+use std::collections::HashMap;
+
+#[derive(Snafu, Debug)]
+pub enum SymbolError {
+    #[snafu(display("Could not read symbol file {}: {}", path, source))]
+    Read { path: String, source: std::io::Error },
+}
+
+#[derive(Default)]
+pub struct Symbols {
+    names: HashMap<String, Location>,
+    labels: Vec<(Location, String)>, // sorted by location
+    skipped: usize,
+}
+
+impl Symbols {
+    pub fn load(path: &str) -> Result<Self, SymbolError> {
+        let content =
+            std::fs::read_to_string(path).map_err(|source| SymbolError::Read { path: path.to_string(), source })?;
+        let symbols = Symbols::parse(&content);
+        if symbols.skipped > 0 {
+            println!("Skipped {} malformed lines in {}", symbols.skipped, path);
+        }
+        Ok(symbols)
+    }
+
+    fn parse(content: &str) -> Self {
+        let mut symbols = Symbols::default();
+        for line in content.lines() {
+            let line = line.split(';').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            match parse_line(line) {
+                Some((loc, name)) => {
+                    symbols.names.insert(name.to_string(), loc);
+                    symbols.labels.push((loc, name.to_string()));
+                }
+                None => symbols.skipped += 1,
+            }
+        }
+        symbols.labels.sort();
+        symbols
+    }
+
+    pub fn location(&self, name: &str) -> Option<Location> {
+        self.names.get(name).copied()
+    }
+
+    // label at addr, in the bank mapped there
+    pub fn label(&self, gb: &GameBoy, addr: u16) -> Option<&str> {
+        let start = self.labels.partition_point(|(loc, _)| loc.addr < addr);
+        self.labels[start..]
+            .iter()
+            .take_while(|(loc, _)| loc.addr == addr)
+            .find(|(loc, _)| loc.mapped(gb))
+            .map(|(_, name)| name.as_str())
+    }
+
+    // closest global label at or before addr, and how far addr is from it
+    pub fn function(&self, gb: &GameBoy, addr: u16) -> Option<(&str, u16)> {
+        let end = self.labels.partition_point(|(loc, _)| loc.addr <= addr);
+        self.labels[..end]
+            .iter()
+            .rev()
+            .find(|(loc, name)| !name.contains('.') && loc.mapped(gb) && same_region(loc.addr, addr))
+            .map(|(loc, name)| (name.as_str(), addr - loc.addr))
+    }
+}
+
+fn parse_line(line: &str) -> Option<(Location, &str)> {
+    let (loc, name) = line.split_once(char::is_whitespace)?;
+    let name = name.trim();
+    if name.contains(char::is_whitespace) {
+        return None;
+    }
+    let (bank, addr) = loc.split_once(':')?;
+    let bank = u16::from_str_radix(bank, 16).ok()?;
+    let addr = u16::from_str_radix(addr, 16).ok()?;
+
+    // RGBDS gives a bank to everything, even what the cartridge doesn't map
+    let bank = match addr {
+        0x0000..=0x7FFF | 0xA000..=0xBFFF => Some(bank),
+        _ => None,
+    };
+    Some((Location { addr, bank }, name))
+}
+
+// a function in ROM0 doesn't go on in ROMX, nor ROM into VRAM, etc
+fn same_region(a: u16, b: u16) -> bool {
+    let region = |addr: u16| match addr {
+        0x0000..=0x3FFF => 0,
+        0x4000..=0x7FFF => 1,
+        0x8000..=0x9FFF => 2,
+        0xA000..=0xBFFF => 3,
+        0xC000..=0xDFFF => 4,
+        _ => 5,
+    };
+    region(a) == region(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::load_program;
+
+    const SYM_FILE: &str = "
+; File generated by rgblink
+
+00:0150 Main
+00:0158 Main.loop ; local labels are written in full
+01:4000 Bank1Func
+02:4000 Bank2Func
+00:c000 wBuffer
+
+this is not a symbol
+zz:0100 BadBank
+00:0100
+00:0200 Two names
+";
+
+    fn loc(symbols: &Symbols, name: &str) -> Option<(u16, Option<u16>)> {
+        symbols.location(name).map(|loc| (loc.addr, loc.bank))
+    }
+
+    #[test]
+    fn parse_lines() {
+        let symbols = Symbols::parse(SYM_FILE);
+        assert_eq!(loc(&symbols, "Main"), Some((0x0150, Some(0))));
+        assert_eq!(loc(&symbols, "Main.loop"), Some((0x0158, Some(0))));
+        assert_eq!(loc(&symbols, "Bank2Func"), Some((0x4000, Some(2))));
+        // WRAM isn't banked by the cartridge
+        assert_eq!(loc(&symbols, "wBuffer"), Some((0xC000, None)));
+    }
+
+    #[test]
+    fn skip_comments_and_malformed_lines() {
+        let symbols = Symbols::parse(SYM_FILE);
+        assert_eq!(symbols.labels.len(), 5);
+        assert_eq!(symbols.skipped, 4);
+        assert_eq!(loc(&symbols, "BadBank"), None);
+        assert_eq!(loc(&symbols, "Two"), None);
+    }
+
+    #[test]
+    fn lookup_labels() {
+        let gb = load_program("symbols_lookup_labels", &[]);
+        let symbols = Symbols::parse(SYM_FILE);
+
+        assert_eq!(symbols.label(&gb, 0x0158), Some("Main.loop"));
+        // only bank 1 is mapped at $4000 without a mapper
+        assert_eq!(symbols.label(&gb, 0x4000), Some("Bank1Func"));
+        assert_eq!(symbols.label(&gb, 0x0151), None);
+
+        // local labels don't start functions
+        assert_eq!(symbols.function(&gb, 0x015A), Some(("Main", 0x0A)));
+        assert_eq!(symbols.function(&gb, 0x4003), Some(("Bank1Func", 3)));
+        assert_eq!(symbols.function(&gb, 0xC004), Some(("wBuffer", 4)));
+        // nothing before Main, and functions don't spill over into other regions
+        assert_eq!(symbols.function(&gb, 0x0100), None);
+        assert_eq!(symbols.function(&gb, 0x8000), None);
+    }
+}
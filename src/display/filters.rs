@@ -0,0 +1,211 @@
+// Software upscaling, run on the RGB frame before it's uploaded to the texture.
+// Every filter scales by an integer factor of at most MAX_SCALE.
+use super::{palettes::Rgb, HEIGHT, WIDTH};
+
+pub const MAX_SCALE: usize = 4;
+
+#[derive(Copy, Clone, Debug)]
+pub enum Filter {
+    NONE,    // left for SDL to stretch
+    NEAREST, // integer scaling to fill the window
+    SCALE2X,
+    SCALE3X,
+    XBRLITE,
+    HQ2X,
+    LCDGRID, // dot matrix look, with dark gaps between pixels
+}
+
+impl Filter {
+    pub fn next(self) -> Self {
+        match self {
+            Filter::NONE => Filter::NEAREST,
+            Filter::NEAREST => Filter::SCALE2X,
+            Filter::SCALE2X => Filter::SCALE3X,
+            Filter::SCALE3X => Filter::XBRLITE,
+            Filter::XBRLITE => Filter::HQ2X,
+            Filter::HQ2X => Filter::LCDGRID,
+            Filter::LCDGRID => Filter::NONE,
+        }
+    }
+
+    pub fn scale(self) -> usize {
+        match self {
+            Filter::NONE => 1,
+            Filter::SCALE2X | Filter::XBRLITE | Filter::HQ2X => 2,
+            Filter::SCALE3X => 3,
+            Filter::NEAREST | Filter::LCDGRID => MAX_SCALE,
+        }
+    }
+
+    // rgb is a WIDTH x HEIGHT frame, the result is scale() times bigger
+    pub fn apply(self, rgb: &[u8]) -> Vec<u8> {
+        if let Filter::NONE = self {
+            return rgb.to_vec();
+        }
+
+        let src = Frame { rgb };
+        let scale = self.scale();
+        let mut out = vec![0; WIDTH * HEIGHT * scale * scale * 3];
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let block = match self {
+                    Filter::NONE | Filter::NEAREST => [src.get(x, y); MAX_SCALE * MAX_SCALE],
+                    Filter::SCALE2X => widen(scale2x(&src.neighbours(x, y))),
+                    Filter::SCALE3X => widen(scale3x(&src.neighbours(x, y))),
+                    Filter::XBRLITE => widen(xbr_lite(&src.neighbours(x, y))),
+                    Filter::HQ2X => widen(hq2x(&src.neighbours(x, y))),
+                    Filter::LCDGRID => lcd_grid(src.get(x, y)),
+                };
+                for by in 0..scale {
+                    for bx in 0..scale {
+                        let idx = ((y * scale + by) * WIDTH * scale + x * scale + bx) * 3;
+                        out[idx..idx + 3].copy_from_slice(&block[by * scale + bx]);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+struct Frame<'a> {
+    rgb: &'a [u8],
+}
+
+impl Frame<'_> {
+    #[inline(always)]
+    fn get(&self, x: usize, y: usize) -> Rgb {
+        let idx = (y * WIDTH + x) * 3;
+        [self.rgb[idx], self.rgb[idx + 1], self.rgb[idx + 2]]
+    }
+
+    // the 3x3 block around (x, y), repeating the edges of the frame:
+    // A B C
+    // D E F
+    // G H I
+    fn neighbours(&self, x: usize, y: usize) -> [Rgb; 9] {
+        let (left, right) = (x.saturating_sub(1), usize::min(x + 1, WIDTH - 1));
+        let (up, down) = (y.saturating_sub(1), usize::min(y + 1, HEIGHT - 1));
+        [
+            self.get(left, up),
+            self.get(x, up),
+            self.get(right, up),
+            self.get(left, y),
+            self.get(x, y),
+            self.get(right, y),
+            self.get(left, down),
+            self.get(x, down),
+            self.get(right, down),
+        ]
+    }
+}
+
+// blocks are stored row by row with a stride equal to the filter's scale,
+// padded to the biggest block
+fn widen<const N: usize>(block: [Rgb; N]) -> [Rgb; MAX_SCALE * MAX_SCALE] {
+    let mut wide = [[0; 3]; MAX_SCALE * MAX_SCALE];
+    wide[..N].copy_from_slice(&block);
+    wide
+}
+
+// EPX, the classic Scale2x
+fn scale2x(n: &[Rgb; 9]) -> [Rgb; 4] {
+    let [_, b, _, d, e, f, _, h, _] = *n;
+    if b == h || d == f {
+        return [e; 4];
+    }
+    [if d == b { d } else { e }, if b == f { f } else { e }, if d == h { d } else { e }, if h == f { f } else { e }]
+}
+
+// AdvMAME3x
+fn scale3x(n: &[Rgb; 9]) -> [Rgb; 9] {
+    let [a, b, c, d, e, f, g, h, i] = *n;
+    if b == h || d == f {
+        return [e; 9];
+    }
+    [
+        if d == b { d } else { e },
+        if (d == b && e != c) || (b == f && e != a) { b } else { e },
+        if b == f { f } else { e },
+        if (d == b && e != g) || (d == h && e != a) { d } else { e },
+        e,
+        if (b == f && e != i) || (h == f && e != c) { f } else { e },
+        if d == h { d } else { e },
+        if (d == h && e != i) || (h == f && e != g) { h } else { e },
+        if h == f { f } else { e },
+    ]
+}
+
+// A cut down 2xBR: each corner looks for an edge crossing it diagonally, by
+// comparing the two diagonals meeting there, and gets blended with the closest
+// of the two neighbours on that edge. Unlike the real thing it only looks at
+// the 3x3 block, so it smooths less but keeps the same character.
+fn xbr_lite(n: &[Rgb; 9]) -> [Rgb; 4] {
+    let [a, b, c, d, e, f, g, h, i] = *n;
+    let corner = |side1: Rgb, side2: Rgb, far: Rgb| {
+        let across = distance(side1, side2) * 2 + distance(e, far);
+        let along = distance(e, far) * 2 + distance(side1, side2);
+        if across >= along || !similar(side1, side2) || similar(e, side1) || similar(e, side2) {
+            return e;
+        }
+        let closest = if distance(e, side1) <= distance(e, side2) { side1 } else { side2 };
+        blend(e, closest, 1, 1)
+    };
+    [corner(b, d, a), corner(b, f, c), corner(d, h, g), corner(f, h, i)]
+}
+
+// HQ2x style interpolation. The original uses a table with a rule for each of
+// the 256 combinations of neighbours differing from the center; this keeps the
+// rules that matter the most: edges get the two sides blended in, corners of
+// flat areas are softened towards their diagonal neighbour.
+fn hq2x(n: &[Rgb; 9]) -> [Rgb; 4] {
+    let [a, b, c, d, e, f, g, h, i] = *n;
+    let corner = |side1: Rgb, side2: Rgb, diag: Rgb| {
+        if similar(side1, side2) && !similar(e, side1) {
+            return blend(e, blend(side1, side2, 1, 1), 2, 2);
+        }
+        if !similar(e, diag) {
+            return blend(e, diag, 3, 1);
+        }
+        e
+    };
+    [corner(b, d, a), corner(b, f, c), corner(d, h, g), corner(f, h, i)]
+}
+
+fn lcd_grid(color: Rgb) -> [Rgb; MAX_SCALE * MAX_SCALE] {
+    let gap = blend(color, [0; 3], 3, 1);
+    let mut block = [color; MAX_SCALE * MAX_SCALE];
+    for i in 0..MAX_SCALE {
+        block[(MAX_SCALE - 1) * MAX_SCALE + i] = gap;
+        block[i * MAX_SCALE + MAX_SCALE - 1] = gap;
+    }
+    block
+}
+
+#[inline(always)]
+fn blend(x: Rgb, y: Rgb, wx: u16, wy: u16) -> Rgb {
+    let mix = |c: usize| ((x[c] as u16 * wx + y[c] as u16 * wy) / (wx + wy)) as u8;
+    [mix(0), mix(1), mix(2)]
+}
+
+#[inline(always)]
+fn yuv(color: Rgb) -> (i32, i32, i32) {
+    let (r, g, b) = (color[0] as i32, color[1] as i32, color[2] as i32);
+    let y = (r * 299 + g * 587 + b * 114) / 1000;
+    let u = (b - y) * 493 / 1000;
+    let v = (r - y) * 877 / 1000;
+    (y, u, v)
+}
+
+#[inline(always)]
+fn distance(x: Rgb, y: Rgb) -> u32 {
+    let ((y1, u1, v1), (y2, u2, v2)) = (yuv(x), yuv(y));
+    (y1 - y2).unsigned_abs() * 48 + (u1 - u2).unsigned_abs() * 7 + (v1 - v2).unsigned_abs() * 6
+}
+
+// thresholds from the hqx family of filters
+#[inline(always)]
+fn similar(x: Rgb, y: Rgb) -> bool {
+    let ((y1, u1, v1), (y2, u2, v2)) = (yuv(x), yuv(y));
+    (y1 - y2).abs() <= 48 && (u1 - u2).abs() <= 7 && (v1 - v2).abs() <= 6
+}
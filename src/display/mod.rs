@@ -0,0 +1,146 @@
+// Turns the framebuffer into what ends up on the main window.
+use crate::{gameboy::GameBoy, ppu::inspect::Palette};
+use filters::Filter;
+use palettes::ColorScheme;
+use recorder::Recorder;
+
+pub mod filters;
+pub mod palettes;
+mod recorder;
+
+pub const WIDTH: usize = 160;
+pub const HEIGHT: usize = 144;
+
+const LAYER_TINTS: [[u8; 3]; 3] = [[0xFF, 0x60, 0x60], [0x60, 0xFF, 0x60], [0x60, 0x60, 0xFF]]; // BG, window, sprites
+
+pub struct Display {
+    schemes: Vec<ColorScheme>,
+    scheme: usize,
+    pub tint_layers: bool,
+    filter: Filter,
+
+    // LCD ghosting: the DMG's pixels take a few frames to change, and games
+    // flicker objects counting on it to look semi-transparent. Each frame,
+    // every pixel moves towards its new color by (1 - persistence) of the
+    // way, an exponential response like the real panel's.
+    persistence: f32,
+    ghosting: bool,
+    shown: Vec<f32>,
+
+    recorder: Option<Recorder>,
+}
+
+impl Display {
+    // Schemes from the palette file come after the built in ones
+    pub fn init(palette_file: Option<&str>, persistence: f32) -> Self {
+        let mut schemes = palettes::builtin();
+        if let Some(path) = palette_file {
+            match palettes::load(path) {
+                Ok(loaded) => schemes.extend(loaded),
+                Err(e) => println!("{}", e),
+            }
+        }
+        Self {
+            schemes,
+            scheme: 0,
+            tint_layers: false,
+            filter: Filter::NONE,
+            persistence: persistence.clamp(0.0, 0.95),
+            ghosting: false,
+            shown: vec![],
+            recorder: None,
+        }
+    }
+
+    pub fn next_scheme(&mut self) {
+        self.scheme = (self.scheme + 1) % self.schemes.len();
+        println!("Palette: {}", self.schemes[self.scheme].name);
+    }
+
+    // color of a shade that went through palette, in the active scheme
+    pub fn color(&self, palette: Palette, shade: u8) -> palettes::Rgb {
+        self.schemes[self.scheme].color(palette, shade)
+    }
+
+    pub fn next_filter(&mut self) {
+        self.filter = self.filter.next();
+        println!("Filter: {:?}", self.filter);
+    }
+
+    pub fn toggle_ghosting(&mut self) {
+        self.ghosting = !self.ghosting;
+        self.shown.clear();
+        println!("Ghosting {}", if self.ghosting { "on" } else { "off" });
+    }
+
+    // Starts recording what's rendered to path, or stops the ongoing recording
+    pub fn toggle_recording(&mut self, path: &str) {
+        match self.recorder.take() {
+            Some(recorder) => recorder.stop(),
+            None => match Recorder::start(path) {
+                Ok(recorder) => self.recorder = Some(recorder),
+                Err(e) => println!("{}", e),
+            },
+        }
+    }
+
+    // Finishes the ongoing recording, if any
+    pub fn stop_recording(&mut self) {
+        if let Some(recorder) = self.recorder.take() {
+            recorder.stop();
+        }
+    }
+
+    // dimensions of the frames returned by render
+    pub fn size(&self) -> (u32, u32) {
+        let scale = self.filter.scale() as u32;
+        (WIDTH as u32 * scale, HEIGHT as u32 * scale)
+    }
+
+    // Meant to be called once per frame, as ghosting blends each call's
+    // result with the previous ones
+    pub fn render(&mut self, gb: &GameBoy) -> Vec<u8> {
+        let scheme = &self.schemes[self.scheme];
+        let mut rgb = vec![0; WIDTH * HEIGHT * 3];
+
+        let palettes = gb.borrow_palettebuffer();
+        for (i, pixel) in gb.borrow_framebuffer().iter().enumerate() {
+            rgb[i * 3..i * 3 + 3].copy_from_slice(&scheme.color(palettes[i], *pixel));
+        }
+
+        // colors each pixel by the layer that drew it
+        if self.tint_layers {
+            for (i, layer) in gb.borrow_layerbuffer().iter().enumerate() {
+                for c in 0..3 {
+                    rgb[(i * 3) + c] = (rgb[(i * 3) + c] as u16 * LAYER_TINTS[*layer as usize][c] as u16 / 0xFF) as u8;
+                }
+            }
+        }
+
+        if self.ghosting {
+            self.blend(&mut rgb);
+        }
+
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(e) = recorder.write_frame(&rgb) {
+                println!("{}", e);
+                self.recorder.take().unwrap().stop();
+            }
+        }
+
+        self.filter.apply(&rgb)
+    }
+
+    fn blend(&mut self, rgb: &mut [u8]) {
+        if self.shown.len() != rgb.len() {
+            self.shown = rgb.iter().map(|c| *c as f32).collect();
+            return;
+        }
+
+        let response = 1.0 - self.persistence;
+        for (shown, c) in self.shown.iter_mut().zip(rgb.iter_mut()) {
+            *shown += (*c as f32 - *shown) * response;
+            *c = shown.round() as u8;
+        }
+    }
+}
@@ -0,0 +1,122 @@
+// Colors used to display the four shades of the DMG. Palette files describe
+// one or more schemes like so, obp0 and obp1 defaulting to bg when missing:
+//
+//     [Name]
+//     bg   = E0F8D0 88C070 346856 081820
+//     obp0 = FFFFFF AAAAAA 555555 000000
+//     obp1 = FFFFFF AAAAAA 555555 000000
+//
+// Lines starting with # are ignored.
+use crate::ppu::inspect::Palette;
+use snafu::Snafu;
+
+pub type Rgb = [u8; 3];
+
+pub const GREYSCALE: [Rgb; 4] = [[0xFF; 3], [0xA9; 3], [0x54; 3], [0x00; 3]];
+
+#[derive(Clone)]
+pub struct ColorScheme {
+    pub name: String,
+    bg: [Rgb; 4],
+    obp0: [Rgb; 4],
+    obp1: [Rgb; 4],
+}
+
+#[derive(Snafu, Debug)]
+pub enum PaletteError {
+    #[snafu(display("Could not read palette file {}: {}", path, source))]
+    Read { path: String, source: std::io::Error },
+
+    #[snafu(display("Palette file line {}: {}", line, msg))]
+    Syntax { line: usize, msg: String },
+}
+
+impl ColorScheme {
+    fn uniform(name: &str, colors: [Rgb; 4]) -> Self {
+        Self { name: name.to_string(), bg: colors, obp0: colors, obp1: colors }
+    }
+
+    #[inline(always)]
+    pub fn color(&self, palette: Palette, shade: u8) -> Rgb {
+        let colors = match palette {
+            Palette::BGP => &self.bg,
+            Palette::OBP0 => &self.obp0,
+            Palette::OBP1 => &self.obp1,
+        };
+        colors[shade as usize & 0x03]
+    }
+}
+
+pub fn builtin() -> Vec<ColorScheme> {
+    vec![
+        ColorScheme::uniform("Grey", GREYSCALE),
+        ColorScheme::uniform("DMG", [[0x9B, 0xBC, 0x0F], [0x8B, 0xAC, 0x0F], [0x30, 0x62, 0x30], [0x0F, 0x38, 0x0F]]),
+        ColorScheme::uniform(
+            "Pocket",
+            [[0xC4, 0xCF, 0xA1], [0x8B, 0x95, 0x6D], [0x4D, 0x53, 0x3C], [0x1F, 0x1F, 0x1F]],
+        ),
+        ColorScheme::uniform("Light", [[0x00, 0xB5, 0x81], [0x00, 0x9A, 0x71], [0x00, 0x69, 0x4A], [0x00, 0x4F, 0x3B]]),
+    ]
+}
+
+pub fn load(path: &str) -> Result<Vec<ColorScheme>, PaletteError> {
+    let content =
+        std::fs::read_to_string(path).map_err(|source| PaletteError::Read { path: path.to_string(), source })?;
+
+    let mut schemes: Vec<ColorScheme> = vec![];
+    // whether obp0 and obp1 were given for the last scheme
+    let mut obj_given = (false, false);
+    for (i, line) in content.lines().enumerate() {
+        let syntax = |msg: &str| PaletteError::Syntax { line: i + 1, msg: msg.to_string() };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            schemes.push(ColorScheme::uniform(name.trim(), GREYSCALE));
+            obj_given = (false, false);
+            continue;
+        }
+
+        let (key, value) = line.split_once('=').ok_or_else(|| syntax("expected `key = colors`"))?;
+        let scheme = schemes.last_mut().ok_or_else(|| syntax("colors given before any [name]"))?;
+        let colors = parse_colors(value).ok_or_else(|| syntax("expected 4 colors as RRGGBB"))?;
+        match key.trim() {
+            "bg" => {
+                scheme.bg = colors;
+                if !obj_given.0 {
+                    scheme.obp0 = colors;
+                }
+                if !obj_given.1 {
+                    scheme.obp1 = colors;
+                }
+            }
+            "obp0" => {
+                scheme.obp0 = colors;
+                obj_given.0 = true;
+            }
+            "obp1" => {
+                scheme.obp1 = colors;
+                obj_given.1 = true;
+            }
+            key => return Err(syntax(&format!("unknown palette `{}`", key))),
+        }
+    }
+    Ok(schemes)
+}
+
+fn parse_colors(value: &str) -> Option<[Rgb; 4]> {
+    let colors: Vec<&str> = value.split_whitespace().collect();
+    if colors.len() != 4 {
+        return None;
+    }
+
+    let mut rgb = [[0; 3]; 4];
+    for (color, hex) in rgb.iter_mut().zip(colors) {
+        let hex = hex.trim_start_matches('#');
+        let val = u32::from_str_radix(hex, 16).ok().filter(|_| hex.len() == 6)?;
+        *color = [(val >> 16) as u8, (val >> 8) as u8, val as u8];
+    }
+    Some(rgb)
+}
@@ -0,0 +1,115 @@
+// Records what's shown on screen, before upscaling, to a file. Y4M is raw video
+// that ffmpeg takes as is, and is cheap enough to keep up with the emulator;
+// GIF is handier to share but much slower to encode. Audio will go to a WAV
+// file next to the video once there is an APU to record.
+use super::{HEIGHT, WIDTH};
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, RgbaImage,
+};
+use snafu::Snafu;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+// the DMG runs at 4194304Hz, with 70224 cycles per frame
+const CLOCK: u32 = 4194304;
+const FRAME_CYCLES: u32 = 70224;
+
+#[derive(Snafu, Debug)]
+pub enum RecordError {
+    #[snafu(display("Could not write recording {}: {}", path, source))]
+    Io { path: String, source: std::io::Error },
+
+    #[snafu(display("Could not encode recording {}: {}", path, source))]
+    Encoding { path: String, source: image::ImageError },
+}
+
+enum Encoder {
+    Y4M(BufWriter<File>),
+    GIF(Box<GifEncoder<BufWriter<File>>>),
+}
+
+pub struct Recorder {
+    path: String,
+    encoder: Encoder,
+    frames: u64,
+}
+
+impl Recorder {
+    // The format is picked from the extension: GIF for .gif, Y4M otherwise
+    pub fn start(path: &str) -> Result<Self, RecordError> {
+        let io_err = |source| RecordError::Io { path: path.to_string(), source };
+        let file = BufWriter::new(File::create(path).map_err(io_err)?);
+
+        let encoder = match path.ends_with(".gif") {
+            true => {
+                let mut gif = GifEncoder::new_with_speed(file, 10);
+                gif.set_repeat(Repeat::Infinite)
+                    .map_err(|source| RecordError::Encoding { path: path.to_string(), source })?;
+                Encoder::GIF(Box::new(gif))
+            }
+            false => {
+                let mut file = file;
+                // full range BT.601 4:4:4, so colors barely change
+                writeln!(
+                    file,
+                    "YUV4MPEG2 W{} H{} F{}:{} Ip A1:1 C444 XCOLORRANGE=FULL",
+                    WIDTH, HEIGHT, CLOCK, FRAME_CYCLES
+                )
+                .map_err(io_err)?;
+                Encoder::Y4M(file)
+            }
+        };
+
+        println!("Recording to {}", path);
+        Ok(Self { path: path.to_string(), encoder, frames: 0 })
+    }
+
+    // rgb is a WIDTH x HEIGHT frame
+    pub fn write_frame(&mut self, rgb: &[u8]) -> Result<(), RecordError> {
+        self.frames += 1;
+        match &mut self.encoder {
+            Encoder::Y4M(file) => {
+                let mut planes = vec![0; WIDTH * HEIGHT * 3];
+                let (y_plane, uv) = planes.split_at_mut(WIDTH * HEIGHT);
+                let (u_plane, v_plane) = uv.split_at_mut(WIDTH * HEIGHT);
+                for (i, pixel) in rgb.chunks_exact(3).enumerate() {
+                    (y_plane[i], u_plane[i], v_plane[i]) = to_yuv(pixel[0], pixel[1], pixel[2]);
+                }
+                file.write_all(b"FRAME\n")
+                    .and_then(|_| file.write_all(&planes))
+                    .map_err(|source| RecordError::Io { path: self.path.clone(), source })
+            }
+            Encoder::GIF(gif) => {
+                let mut rgba = RgbaImage::new(WIDTH as u32, HEIGHT as u32);
+                for (dst, src) in rgba.pixels_mut().zip(rgb.chunks_exact(3)) {
+                    dst.0 = [src[0], src[1], src[2], 0xFF];
+                }
+                // GIF delays are in hundredths of a second, so frames last 1 or
+                // 2 of them to keep in step with the real frame rate
+                let centis = |frames: u64| (frames * FRAME_CYCLES as u64 * 100 + CLOCK as u64 / 2) / CLOCK as u64;
+                let delay = Delay::from_numer_denom_ms((centis(self.frames) - centis(self.frames - 1)) as u32 * 10, 1);
+                gif.encode_frame(Frame::from_parts(rgba, 0, 0, delay))
+                    .map_err(|source| RecordError::Encoding { path: self.path.clone(), source })
+            }
+        }
+    }
+
+    pub fn stop(mut self) {
+        if let Encoder::Y4M(file) = &mut self.encoder {
+            _ = file.flush();
+        }
+        println!("Recorded {} frames to {}", self.frames, self.path);
+    }
+}
+
+#[inline(always)]
+fn to_yuv(r: u8, g: u8, b: u8) -> (u8, u8, u8) {
+    let (r, g, b) = (r as f32, g as f32, b as f32);
+    let y = 0.299 * r + 0.587 * g + 0.114 * b;
+    let u = 128.0 + (b - y) * 0.564;
+    let v = 128.0 + (r - y) * 0.713;
+    (y.round() as u8, u.round().clamp(0.0, 255.0) as u8, v.round().clamp(0.0, 255.0) as u8)
+}
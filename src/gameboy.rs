@@ -1,9 +1,10 @@
 use crate::{
-    cpu::Cpu,
+    cpu::{calls::CallStack, Cpu},
     intr::InterruptHandler,
     mmu::{
         cart,
         cart::CartridgeEnum,
+        hooks::AccessHooks,
         io::{joypad::Joypad, serial::SerialLink, timer::Timer},
         mem::{hram::HRam, unused::Unused, wram0::WRam0, wramx::WRamX, MemoryUnit},
     },
@@ -12,8 +13,10 @@ use crate::{
 
 pub struct GameBoy {
     pub cpu: Cpu,
+    pub calls: CallStack,
     pub halt: bool,
     pub halt_bug: bool,
+    pub stop: bool,
 
     pub intr: InterruptHandler,
 
@@ -22,6 +25,7 @@ pub struct GameBoy {
     pub wramx: WRamX,
     pub _unused: Unused, // Currently unused, but will be needed for CGB implementation
     pub hram: HRam,
+    pub hooks: AccessHooks,
 
     pub ppu: Ppu,
 
@@ -34,8 +38,10 @@ impl GameBoy {
     pub fn init(path: &str) -> Self {
         Self {
             cpu: Cpu { a: 0x01, f: 0xB0, b: 0x00, c: 0x13, d: 0x00, e: 0xD8, h: 0x01, l: 0x4D, sp: 0xFFFE, pc: 0x100 },
+            calls: CallStack::init(),
             halt: false,
             halt_bug: false,
+            stop: false,
             intr: InterruptHandler::init(),
 
             cart: cart::load_rom_file(path),
@@ -43,6 +49,7 @@ impl GameBoy {
             wramx: MemoryUnit::init(),
             _unused: MemoryUnit::init(),
             hram: MemoryUnit::init(),
+            hooks: AccessHooks::init(),
 
             ppu: Ppu::init(),
 
@@ -56,11 +63,12 @@ impl GameBoy {
         self.cycle_timer(cycles);
         self.cycle_joypad(cycles);
         self.cycle_ppu(cycles);
+        self.cycle_cart(cycles);
     }
 
     #[inline(always)]
     pub fn dpc(&self, offset: i8) -> u8 {
-        self.read(u16::wrapping_add(self.cpu.pc, offset as u16))
+        self.fetch(u16::wrapping_add(self.cpu.pc, offset as u16))
     }
 
     #[inline(always)]
@@ -71,7 +79,8 @@ impl GameBoy {
 
     #[inline(always)]
     pub fn cycle_dpc(&mut self, offset: i8) -> u8 {
-        self.cycle_read(u16::wrapping_add(self.cpu.pc, offset as u16))
+        self.advance_cycles(4);
+        self.dpc(offset)
     }
 
     #[inline(always)]
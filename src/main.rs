@@ -1,23 +1,38 @@
-use crate::{debug::Debugger, gameboy::GameBoy, mmu::io::joypad::Button};
+use crate::{
+    bindings::{Action, Bindings, Hotkey},
+    debug::Debugger,
+    display::{filters::MAX_SCALE, Display},
+    gameboy::GameBoy,
+    mmu::{cart::StaticImage, io::joypad::Button},
+    movie::{Movie, MovieMode},
+    ppu::Layer,
+    views::{ObjectView, TileView, TilemapView, ViewEnum, ViewTrait},
+};
 use sdl2::{
     controller,
     controller::GameController,
-    event::Event,
-    keyboard::Keycode,
+    event::{Event, WindowEvent},
+    keyboard::Mod,
+    mouse::MouseButton,
     pixels::{Color, PixelFormatEnum},
+    rect::Rect,
     render::{Canvas, Texture, TextureAccess},
     video::Window,
     GameControllerSubsystem, Sdl,
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
 
+mod bindings;
 mod cpu;
 mod debug;
+mod display;
 mod gameboy;
 mod intr;
 mod mmu;
+mod movie;
 mod ppu;
 mod test;
+mod views;
 
 extern crate num;
 extern crate num_derive;
@@ -27,34 +42,80 @@ extern crate snafu;
 
 const PATH: &str = "src/test/dmg-acid2.gb";
 const DEBUG: bool = false;
-const PALETTE: [u8; 4] = [0xFF, 0xA9, 0x54, 0x00];
+const VIEWS: bool = false; // opens debugging windows for VRAM, OAM, etc
+const UNLIMITED_SPRITES: bool = false; // draws objects past the 10 per line limit
+const GHOSTING: f32 = 0.5; // share of the previous frames kept by LCD ghosting, toggled with F7
+const RECORDING: Option<&str> = None; // records from power on to this .y4m or .gif file
+const MOVIE_PLAY: Option<&str> = None; // input movie played from power on
+const MOVIE_RECORD: Option<&str> = None; // records an input movie, saved on quitting or with F10
+const BINDINGS_FILE: Option<&str> = None; // keys and buttons, see bindings.rs
+const PALETTE_FILE: Option<&str> = None; // extra color schemes, see display/palettes.rs
+const CAMERA_IMAGE: Option<&str> = None; // picture seen by the Pocket Camera, a test pattern if None
 
 fn main() {
     let mut gb = GameBoy::init(PATH);
+    if !movie_session() {
+        if let Err(e) = gb.load_battery(&save_path()) {
+            println!("Could not load save: {}", e);
+        }
+    }
+    gb.set_unlimited_sprites(UNLIMITED_SPRITES);
+    if let Some(path) = CAMERA_IMAGE {
+        gb.set_image_source(Box::new(StaticImage::load(path).unwrap()));
+    }
     let (sdl, mut canvas) = init_renderer();
 
     let tex_creator = canvas.texture_creator();
-    let mut tex = tex_creator.create_texture(PixelFormatEnum::RGB24, TextureAccess::Streaming, 160, 144).unwrap();
-    update_tex(&mut tex, &gb);
+    let (tex_width, tex_height) = (160 * MAX_SCALE as u32, 144 * MAX_SCALE as u32);
+    let mut tex =
+        tex_creator.create_texture(PixelFormatEnum::RGB24, TextureAccess::Streaming, tex_width, tex_height).unwrap();
+    let mut display = Display::init(PALETTE_FILE, GHOSTING);
+    if let Some(path) = RECORDING {
+        display.toggle_recording(path);
+    }
+    update_tex(&mut tex, &gb, &mut display);
+
+    let mut input = init_input(&sdl);
 
-    let (ctrl, mut controllers) = init_ctrl(&sdl);
+    let mut views: Vec<ViewEnum> = match VIEWS {
+        true => vec![TileView::init(&sdl).into(), TilemapView::init(&sdl).into(), ObjectView::init(&sdl).into()],
+        false => vec![],
+    };
+    let mut movie = init_movie(&mut gb);
+    let mut last_frame = gb.frame_count();
 
     match DEBUG {
         true => {
-            let mut dbg = Debugger::init();
+            let mut dbg = Debugger::init(PATH);
             loop {
                 dbg.prompt(&mut gb);
-                handle_events(&sdl, &ctrl, &mut gb, &mut controllers);
-                update_tex(&mut tex, &gb);
-                canvas.copy(&tex, None, None).unwrap();
+                // ghosting and recording expect a single update per frame
+                if gb.frame_count() != last_frame {
+                    last_frame = gb.frame_count();
+                    update_turbo(&mut gb, &mut input, &mut movie);
+                    update_movie(&mut gb, &mut movie);
+                    update_tex(&mut tex, &gb, &mut display);
+                }
+                handle_events(&sdl, canvas.window(), &mut gb, &mut input, &mut display, &mut views, &mut movie);
+                update_rumble(&gb, &mut input);
+                canvas.copy(&tex, display_rect(&display), None).unwrap();
                 canvas.present();
+                update_views(&mut views, &gb);
             }
         }
         false => loop {
             gb.cpu_step();
-            handle_events(&sdl, &ctrl, &mut gb, &mut controllers);
-            update_tex(&mut tex, &gb);
-            canvas.copy(&tex, None, None).unwrap();
+            handle_events(&sdl, canvas.window(), &mut gb, &mut input, &mut display, &mut views, &mut movie);
+            update_rumble(&gb, &mut input);
+            // filters are too slow to run after every instruction
+            if gb.frame_count() != last_frame {
+                last_frame = gb.frame_count();
+                update_turbo(&mut gb, &mut input, &mut movie);
+                update_movie(&mut gb, &mut movie);
+                update_tex(&mut tex, &gb, &mut display);
+                update_views(&mut views, &gb);
+            }
+            canvas.copy(&tex, display_rect(&display), None).unwrap();
             canvas.present();
         },
     }
@@ -71,92 +132,157 @@ fn init_renderer() -> (Sdl, Canvas<Window>) {
     (sdl, canvas)
 }
 
-fn init_ctrl(sdl: &Sdl) -> (GameControllerSubsystem, HashMap<u32, GameController>) {
-    let ctrl = sdl.game_controller().unwrap();
-    ctrl.load_mappings("gamecontrollerdb.txt").unwrap();
-    let controllers = HashMap::new();
-    (ctrl, controllers)
+struct Input {
+    bindings: Bindings,
+    subsystem: GameControllerSubsystem,
+    devices: HashMap<u32, GameController>,
+    tilt: (f32, f32),
+    stick: (i16, i16),
+    held: u8,       // buttons held through regular bindings
+    stick_dirs: u8, // d-pad buttons held through the stick
+    turbo: u8,      // buttons held through turbo bindings
+    turbo_frame: u32,
+    rumble: bool,
+}
+
+fn init_input(sdl: &Sdl) -> Input {
+    let bindings = match Bindings::load(BINDINGS_FILE) {
+        Ok(bindings) => bindings,
+        Err(e) => panic!("{}", e),
+    };
+    let subsystem = sdl.game_controller().unwrap();
+    subsystem.load_mappings("gamecontrollerdb.txt").unwrap();
+    Input {
+        bindings,
+        subsystem,
+        devices: HashMap::new(),
+        tilt: (0.0, 0.0),
+        stick: (0, 0),
+        held: 0,
+        stick_dirs: 0,
+        turbo: 0,
+        turbo_frame: 0,
+        rumble: false,
+    }
 }
 
 #[inline(always)]
-fn update_tex(tex: &mut Texture, gb: &GameBoy) {
-    let fb = gb.borrow_framebuffer();
-    let mut tex_buf = [0; 160 * 144 * 3];
+fn update_rumble(gb: &GameBoy, input: &mut Input) {
+    if gb.rumble() == input.rumble {
+        return;
+    }
+    input.rumble = gb.rumble();
 
-    for (i, pixel) in fb.iter().enumerate() {
-        tex_buf[(i * 3) + 0] = PALETTE[*pixel as usize];
-        tex_buf[(i * 3) + 1] = PALETTE[*pixel as usize];
-        tex_buf[(i * 3) + 2] = PALETTE[*pixel as usize];
+    // games drive the motor in short pulses, the duration is only a safety net
+    // in case we miss it being turned off
+    let strength = if input.rumble { 0xFFFF } else { 0 };
+    for controller in input.devices.values_mut() {
+        _ = controller.set_rumble(strength, strength, 1000);
     }
+}
+
+fn update_views(views: &mut [ViewEnum], gb: &GameBoy) {
+    for view in views.iter_mut() {
+        view.update(gb);
+    }
+}
 
-    tex.update(None, &tex_buf, 160 * 3).unwrap();
+#[inline(always)]
+fn update_tex(tex: &mut Texture, gb: &GameBoy, display: &mut Display) {
+    let (width, _) = display.size();
+    tex.update(display_rect(display), &display.render(gb), width as usize * 3).unwrap();
+}
+
+// the part of the texture holding the last frame, which depends on the filter
+#[inline(always)]
+fn display_rect(display: &Display) -> Rect {
+    let (width, height) = display.size();
+    Rect::new(0, 0, width, height)
 }
 
 #[inline(always)]
 fn handle_events(
     sdl: &Sdl,
-    ctrl: &GameControllerSubsystem,
+    window: &Window,
     gb: &mut GameBoy,
-    controllers: &mut HashMap<u32, GameController>,
+    input: &mut Input,
+    display: &mut Display,
+    views: &mut [ViewEnum],
+    movie: &mut Option<Movie>,
 ) {
     for event in sdl.event_pump().unwrap().poll_iter() {
+        for view in views.iter_mut() {
+            view.handle_event(&event);
+        }
+
         match event {
-            Event::Quit { .. } | Event::KeyDown { keycode: Some(Keycode::Escape), .. } => std::process::exit(0),
-
-            Event::KeyDown { keycode: Some(Keycode::Z), .. } => gb.set_button(Button::A, true),
-            Event::KeyDown { keycode: Some(Keycode::X), .. } => gb.set_button(Button::B, true),
-            Event::KeyDown { keycode: Some(Keycode::Return), .. } => gb.set_button(Button::START, true),
-            Event::KeyDown { keycode: Some(Keycode::Backspace), .. } => gb.set_button(Button::SELECT, true),
-            Event::KeyDown { keycode: Some(Keycode::Up), .. } => gb.set_button(Button::UP, true),
-            Event::KeyDown { keycode: Some(Keycode::Down), .. } => gb.set_button(Button::DOWN, true),
-            Event::KeyDown { keycode: Some(Keycode::Left), .. } => gb.set_button(Button::LEFT, true),
-            Event::KeyDown { keycode: Some(Keycode::Right), .. } => gb.set_button(Button::RIGHT, true),
-
-            Event::KeyUp { keycode: Some(Keycode::Z), .. } => gb.set_button(Button::A, false),
-            Event::KeyUp { keycode: Some(Keycode::X), .. } => gb.set_button(Button::B, false),
-            Event::KeyUp { keycode: Some(Keycode::Return), .. } => gb.set_button(Button::START, false),
-            Event::KeyUp { keycode: Some(Keycode::Backspace), .. } => gb.set_button(Button::SELECT, false),
-            Event::KeyUp { keycode: Some(Keycode::Up), .. } => gb.set_button(Button::UP, false),
-            Event::KeyUp { keycode: Some(Keycode::Down), .. } => gb.set_button(Button::DOWN, false),
-            Event::KeyUp { keycode: Some(Keycode::Left), .. } => gb.set_button(Button::LEFT, false),
-            Event::KeyUp { keycode: Some(Keycode::Right), .. } => gb.set_button(Button::RIGHT, false),
-
-            Event::ControllerButtonDown { button: controller::Button::A, .. } => gb.set_button(Button::A, true),
-            Event::ControllerButtonDown { button: controller::Button::B, .. } => gb.set_button(Button::B, true),
-            Event::ControllerButtonDown { button: controller::Button::Start, .. } => gb.set_button(Button::START, true),
-            Event::ControllerButtonDown { button: controller::Button::Back, .. } => gb.set_button(Button::SELECT, true),
-            Event::ControllerButtonDown { button: controller::Button::DPadUp, .. } => gb.set_button(Button::UP, true),
-            Event::ControllerButtonDown { button: controller::Button::DPadDown, .. } => {
-                gb.set_button(Button::DOWN, true)
-            }
-            Event::ControllerButtonDown { button: controller::Button::DPadLeft, .. } => {
-                gb.set_button(Button::LEFT, true)
-            }
-            Event::ControllerButtonDown { button: controller::Button::DPadRight, .. } => {
-                gb.set_button(Button::RIGHT, true)
-            }
-
-            Event::ControllerButtonUp { button: controller::Button::A, .. } => gb.set_button(Button::A, false),
-            Event::ControllerButtonUp { button: controller::Button::B, .. } => gb.set_button(Button::B, false),
-            Event::ControllerButtonUp { button: controller::Button::Start, .. } => gb.set_button(Button::START, false),
-            Event::ControllerButtonUp { button: controller::Button::Back, .. } => gb.set_button(Button::SELECT, false),
-            Event::ControllerButtonUp { button: controller::Button::DPadUp, .. } => gb.set_button(Button::UP, false),
-            Event::ControllerButtonUp { button: controller::Button::DPadDown, .. } => {
-                gb.set_button(Button::DOWN, false)
-            }
-            Event::ControllerButtonUp { button: controller::Button::DPadLeft, .. } => {
-                gb.set_button(Button::LEFT, false)
-            }
-            Event::ControllerButtonUp { button: controller::Button::DPadRight, .. } => {
-                gb.set_button(Button::RIGHT, false)
+            Event::Quit { .. } => quit(gb, display, movie),
+            // closing a debugging window only hides it, but closing the main one quits
+            Event::Window { win_event: WindowEvent::Close, window_id, .. }
+                if !views.iter().any(|view| view.window_id() == window_id) =>
+            {
+                quit(gb, display, movie)
+            }
+
+            // keys pressed in the debugging windows are theirs (the tile view saves with S),
+            // but buttons get released wherever the focus went
+            Event::KeyDown { window_id, keycode: Some(key), keymod, repeat: false, .. } if window_id == window.id() => {
+                let shift = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+                if let Some(action) = input.bindings.key(key, shift).or_else(|| input.bindings.key(key, false)) {
+                    trigger(action, true, gb, input, display, movie);
+                }
+            }
+            // and even if shift changed while holding them
+            Event::KeyUp { keycode: Some(key), .. } => {
+                for shift in [false, true] {
+                    if let Some(action) = input.bindings.key(key, shift) {
+                        trigger(action, false, gb, input, display, movie);
+                    }
+                }
+            }
+
+            Event::ControllerButtonDown { button, .. } => {
+                if let Some(action) = input.bindings.pad(button) {
+                    trigger(action, true, gb, input, display, movie);
+                }
+            }
+            Event::ControllerButtonUp { button, .. } => {
+                if let Some(action) = input.bindings.pad(button) {
+                    trigger(action, false, gb, input, display, movie);
+                }
+            }
+
+            // accelerometer (MBC7): dragging the mouse away from the center of the
+            // window or moving the left stick tilts the cartridge
+            Event::MouseMotion { window_id, mousestate, x, y, .. } if window_id == window.id() && mousestate.left() => {
+                let (width, height) = window.size();
+                let (half_x, half_y) = (width as f32 / 2.0, height as f32 / 2.0);
+                input.tilt = ((x as f32 - half_x) / half_x, (y as f32 - half_y) / half_y);
+                gb.set_accelerometer(input.tilt.0, input.tilt.1);
+            }
+            Event::MouseButtonUp { window_id, mouse_btn: MouseButton::Left, .. } if window_id == window.id() => {
+                input.tilt = (0.0, 0.0);
+                gb.set_accelerometer(input.tilt.0, input.tilt.1);
+            }
+            Event::ControllerAxisMotion { axis: controller::Axis::LeftX, value, .. } => {
+                input.tilt.0 = value as f32 / i16::MAX as f32;
+                gb.set_accelerometer(input.tilt.0, input.tilt.1);
+                input.stick.0 = value;
+                update_stick(gb, input, movie);
+            }
+            Event::ControllerAxisMotion { axis: controller::Axis::LeftY, value, .. } => {
+                input.tilt.1 = value as f32 / i16::MAX as f32;
+                gb.set_accelerometer(input.tilt.0, input.tilt.1);
+                input.stick.1 = value;
+                update_stick(gb, input, movie);
             }
 
             Event::ControllerDeviceAdded { which, .. } => {
-                controllers.insert(which, ctrl.open(which).unwrap());
+                input.devices.insert(which, input.subsystem.open(which).unwrap());
                 println!("Inserted controller {}", which);
             }
             Event::ControllerDeviceRemoved { which, .. } => {
-                controllers.remove(&which);
+                input.devices.remove(&which);
                 println!("Removed controller {}", which);
             }
 
@@ -164,3 +290,195 @@ fn handle_events(
         }
     }
 }
+
+// held is false when the input bound to action is released
+fn trigger(
+    action: Action,
+    held: bool,
+    gb: &mut GameBoy,
+    input: &mut Input,
+    display: &mut Display,
+    movie: &mut Option<Movie>,
+) {
+    match action {
+        Action::BUTTON(button) => {
+            match held {
+                true => input.held |= button as u8,
+                false => input.held &= !(button as u8),
+            }
+            apply_buttons(gb, input, movie);
+        }
+        Action::TURBO(button) => {
+            match held {
+                true => input.turbo |= button as u8,
+                false => input.turbo &= !(button as u8),
+            }
+            input.turbo_frame = 0;
+            apply_buttons(gb, input, movie);
+        }
+        Action::HOTKEY(hotkey) if held => run_hotkey(hotkey, gb, display, movie),
+        Action::HOTKEY(_) => {}
+    }
+}
+
+// Turbo buttons get pressed and released as frames go by
+fn update_turbo(gb: &mut GameBoy, input: &mut Input, movie: &mut Option<Movie>) {
+    if input.turbo == 0 {
+        return;
+    }
+
+    let (on, off) = input.bindings.turbo_rate;
+    input.turbo_frame = (input.turbo_frame + 1) % (on + off);
+    apply_buttons(gb, input, movie);
+}
+
+fn run_hotkey(hotkey: Hotkey, gb: &mut GameBoy, display: &mut Display, movie: &mut Option<Movie>) {
+    match hotkey {
+        Hotkey::QUIT => quit(gb, display, movie),
+        // layer toggles, for debugging and clean screenshots
+        Hotkey::TOGGLEBACKGROUND => toggle_layer(gb, Layer::BACKGROUND),
+        Hotkey::TOGGLEWINDOW => toggle_layer(gb, Layer::WINDOW),
+        Hotkey::TOGGLESPRITES => toggle_layer(gb, Layer::SPRITES),
+        Hotkey::TINTLAYERS => display.tint_layers = !display.tint_layers,
+        Hotkey::NEXTPALETTE => display.next_scheme(),
+        Hotkey::NEXTFILTER => display.next_filter(),
+        Hotkey::GHOSTING => display.toggle_ghosting(),
+        Hotkey::RECORD => record(gb, display, false),
+        Hotkey::RECORDGIF => record(gb, display, true),
+        Hotkey::STOPMOVIE => stop_movie(movie),
+        Hotkey::SCREENSHOT => screenshot(gb, display, false),
+        Hotkey::RAWSCREENSHOT => screenshot(gb, display, true),
+    }
+}
+
+// The left stick as a d-pad, once it's tilted past the deadzone
+fn update_stick(gb: &mut GameBoy, input: &mut Input, movie: &mut Option<Movie>) {
+    let deadzone = input.bindings.stick_deadzone;
+    if deadzone <= 0 {
+        return;
+    }
+
+    let (x, y) = input.stick;
+    for (button, held) in [
+        (Button::LEFT, x < -deadzone),
+        (Button::RIGHT, x > deadzone),
+        (Button::UP, y < -deadzone),
+        (Button::DOWN, y > deadzone),
+    ] {
+        match held {
+            true => input.stick_dirs |= button as u8,
+            false => input.stick_dirs &= !(button as u8),
+        }
+    }
+    apply_buttons(gb, input, movie);
+}
+
+fn record(gb: &GameBoy, display: &mut Display, gif: bool) {
+    display.toggle_recording(&format!("recording-{}.{}", gb.frame_count(), if gif { "gif" } else { "y4m" }));
+}
+
+// raw saves the shades instead of what's on screen
+fn screenshot(gb: &GameBoy, display: &Display, raw: bool) {
+    let path = format!("screenshot-{}{}.png", gb.frame_count(), if raw { "-raw" } else { "" });
+    let result = match raw {
+        true => gb.save_raw_screenshot(&path),
+        false => gb.save_screenshot(&path, |palette, shade| display.color(palette, shade)),
+    };
+    match result {
+        Ok(()) => println!("Saved screenshot to {}", path),
+        Err(e) => println!("Failed to save screenshot: {}", e),
+    }
+}
+
+// Movies are fed once per frame by the main loop, which the debugger can run
+// past in a single command, so they are only available outside of it
+fn init_movie(gb: &mut GameBoy) -> Option<Movie> {
+    if DEBUG && (MOVIE_PLAY.is_some() || MOVIE_RECORD.is_some()) {
+        println!("Movies can't be played or recorded in debug mode");
+        return None;
+    }
+    let movie = match (MOVIE_PLAY, MOVIE_RECORD) {
+        (Some(path), _) => Movie::load(path, PATH),
+        (None, Some(_)) => Movie::record(PATH),
+        (None, None) => return None,
+    };
+    let mut movie = match movie {
+        Ok(movie) => Some(movie),
+        Err(e) => panic!("{}", e),
+    };
+    update_movie(gb, &mut movie);
+    movie
+}
+
+fn update_movie(gb: &mut GameBoy, movie: &mut Option<Movie>) {
+    match movie.as_mut().map(|movie| movie.next_frame(gb)) {
+        None | Some(Ok(true)) => {}
+        Some(Ok(false)) => {
+            println!("Movie finished");
+            *movie = None;
+        }
+        Some(Err(e)) => {
+            println!("{}", e);
+            *movie = None;
+        }
+    }
+}
+
+// Recordings get saved, playback hands control back to the player
+fn stop_movie(movie: &mut Option<Movie>) {
+    if let Some(movie) = movie.take() {
+        match (movie.mode(), MOVIE_RECORD) {
+            (MovieMode::RECORDING, Some(path)) => _ = movie.save(path).map_err(|e| println!("{}", e)),
+            _ => println!("Movie stopped"),
+        }
+    }
+}
+
+// exiting skips destructors, so anything still being written is finished here
+fn quit(gb: &GameBoy, display: &mut Display, movie: &mut Option<Movie>) -> ! {
+    stop_movie(movie);
+    display.stop_recording();
+    if !movie_session() {
+        if let Err(e) = gb.save_battery(&save_path()) {
+            println!("Could not save: {}", e);
+        }
+    }
+    std::process::exit(0)
+}
+
+// Movies start from power on with no save, and must not overwrite the real
+// one, even after they stop
+fn movie_session() -> bool {
+    !DEBUG && (MOVIE_PLAY.is_some() || MOVIE_RECORD.is_some())
+}
+
+// battery backed state goes next to the ROM
+fn save_path() -> String {
+    Path::new(PATH).with_extension("sav").to_string_lossy().to_string()
+}
+
+// While a movie plays the player has no say, and while one is being recorded
+// input goes through it, so it only reaches the Game Boy between frames
+// A button is pressed as long as any of the inputs bound to it is held, with
+// turbo buttons only counting during the "on" part of their cycle
+fn apply_buttons(gb: &mut GameBoy, input: &Input, movie: &mut Option<Movie>) {
+    let turbo = match input.turbo_frame < input.bindings.turbo_rate.0 {
+        true => input.turbo,
+        false => 0,
+    };
+    let pressed = input.held | input.stick_dirs | turbo;
+    match movie {
+        Some(movie) => {
+            if let MovieMode::RECORDING = movie.mode() {
+                movie.set_buttons(pressed);
+            }
+        }
+        None => gb.set_buttons(pressed),
+    }
+}
+
+fn toggle_layer(gb: &mut GameBoy, layer: Layer) {
+    let shown = !gb.layer_shown(layer);
+    gb.set_layer_shown(layer, shown);
+    println!("{:?} {}", layer, if shown { "shown" } else { "hidden" });
+}
@@ -0,0 +1,116 @@
+use crate::mmu::cart::{CartridgeError, CartridgeTrait, RamBank, RomBank, BLANK_RAM, BLANK_ROM, NO_BANK};
+
+pub struct HuC1 {
+    rom: Vec<RomBank>,
+    ram: Vec<RamBank>,
+
+    rom_bank: u8,
+    rom_bank_mask: u8,
+    ram_bank: u8,
+    ir_mode: bool,
+}
+
+impl HuC1 {
+    pub fn init() -> Self {
+        Self { rom: vec![], ram: vec![], rom_bank: 1, rom_bank_mask: 0, ram_bank: 0, ir_mode: false }
+    }
+}
+
+impl CartridgeTrait for HuC1 {
+    fn init_rom_banks(&mut self, nbanks: u16, raw_rom: &Vec<u8>) -> Result<(), CartridgeError> {
+        if nbanks > 64 {
+            return Err(CartridgeError::InvalidCombination {
+                tp: "HuC1".to_string(),
+                feat: "more than 64 banks of ROM".to_string(),
+            });
+        }
+
+        if (nbanks as usize) * 0x4000 < raw_rom.len() {
+            return Err(CartridgeError::OutOfRomBanks { nbanks, rom_size: raw_rom.len() / 1024 });
+        }
+
+        self.rom = vec![BLANK_ROM; nbanks as usize];
+
+        for i in 0..nbanks as usize {
+            for j in 0..0x4000 {
+                self.rom[i][j] = raw_rom[(i * 0x4000) + j];
+            }
+        }
+
+        self.rom_bank_mask = (nbanks - 1) as u8;
+
+        Ok(())
+    }
+
+    fn init_ram_banks(&mut self, nbanks: u16) -> Result<(), CartridgeError> {
+        if nbanks > 4 {
+            return Err(CartridgeError::InvalidCombination {
+                tp: "HuC1".to_string(),
+                feat: "more than 4 banks of RAM".to_string(),
+            });
+        }
+        self.ram = match nbanks {
+            0 => vec![BLANK_RAM; 1], // allocate the minimum ammount to be safe
+            _ => vec![BLANK_RAM; nbanks as usize],
+        };
+
+        Ok(())
+    }
+
+    fn rom0_read(&self, addr: u16) -> u8 {
+        self.rom[0][addr as usize]
+    }
+
+    fn romx_read(&self, addr: u16) -> u8 {
+        self.rom[(self.rom_bank & self.rom_bank_mask) as usize][(addr - 0x4000) as usize]
+    }
+
+    fn sram_read(&self, addr: u16) -> u8 {
+        match self.ir_mode {
+            // there is no IR peer, so the sensor never sees any light (bit 0 clear)
+            true => 0xC0,
+            false => self.ram[self.ram_bank as usize % self.ram.len()][(addr & 0x1FFF) as usize],
+        }
+    }
+
+    fn rom0_write(&mut self, addr: u16, val: u8) {
+        match addr {
+            // unlike MBCs, HuC1 RAM is always accessible, this register only
+            // switches the $A000-$BFFF area between RAM and the IR port
+            0x0000..=0x1FFF => self.ir_mode = val & 0x0F == 0x0E,
+            0x2000..=0x3FFF => self.rom_bank = val & 0x3F,
+            _ => panic!(),
+        }
+
+        if self.rom_bank == 0 {
+            self.rom_bank = 1;
+        }
+    }
+
+    fn romx_write(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x4000..=0x5FFF => self.ram_bank = val & 0x03,
+            0x6000..=0x7FFF => {}
+            _ => panic!(),
+        }
+    }
+
+    fn sram_write(&mut self, addr: u16, val: u8) {
+        match self.ir_mode {
+            true => {} // there is no IR peer to receive what the LED transmits
+            false => {
+                let nbanks = self.ram.len();
+                self.ram[self.ram_bank as usize % nbanks][(addr & 0x1FFF) as usize] = val
+            }
+        }
+    }
+
+    fn mapped_bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => 0,
+            0x4000..=0x7FFF => (self.rom_bank & self.rom_bank_mask) as u16,
+            _ if self.ir_mode => NO_BANK,
+            _ => (self.ram_bank as usize % self.ram.len().max(1)) as u16,
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use crate::mmu::cart::{CartridgeError, CartridgeTrait, RamBank, RomBank, BLANK_RAM, BLANK_ROM, NO_BANK};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CYCLES_PER_MINUTE: u32 = 4194304 * 60;
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+pub struct HuC3 {
+    rom: Vec<RomBank>,
+    ram: Vec<RamBank>,
+    rtc: Rtc,
+
+    rom_bank: u8,
+    rom_bank_mask: u8,
+    ram_bank: u8,
+    mode: u8, // selects what is mapped to $A000-$BFFF
+}
+
+// The HuC3 RTC is a separate chip talked to through a nibble-wide command
+// interface. Time is kept as minutes since midnight plus a day counter, and is
+// transferred to and from a small nibble addressed memory by commands $60/$61.
+// The clock runs off the CPU's cycles while playing, and catches up with the
+// host's time when a save is loaded.
+struct Rtc {
+    minutes: u16,
+    days: u16,
+    cycles: u32, // into the current minute
+
+    mem: [u8; 0x100],
+    addr: u8,
+    last_cmd: u8,
+    response: u8,
+}
+
+impl HuC3 {
+    pub fn init() -> Self {
+        Self {
+            rom: vec![],
+            ram: vec![],
+            rtc: Rtc { minutes: 0, days: 0, cycles: 0, mem: [0; 0x100], addr: 0, last_cmd: 0, response: 0 },
+            rom_bank: 1,
+            rom_bank_mask: 0,
+            ram_bank: 0,
+            mode: 0,
+        }
+    }
+}
+
+impl Rtc {
+    fn tick(&mut self, cycles: u8) {
+        self.cycles += cycles as u32;
+        if self.cycles >= CYCLES_PER_MINUTE {
+            self.cycles -= CYCLES_PER_MINUTE;
+            self.advance(1);
+        }
+    }
+
+    fn advance(&mut self, minutes: u64) {
+        let total = self.minutes as u64 + minutes;
+        self.minutes = (total % MINUTES_PER_DAY as u64) as u16;
+        self.days = u16::wrapping_add(self.days, (total / MINUTES_PER_DAY as u64) as u16);
+    }
+
+    fn command(&mut self, val: u8) {
+        let arg = val & 0x0F;
+        match val >> 4 {
+            // read nibble and increment address
+            0x1 => {
+                self.response = self.mem[self.addr as usize] & 0x0F;
+                self.addr = u8::wrapping_add(self.addr, 1);
+            }
+            // write nibble and increment address
+            0x3 => {
+                self.mem[self.addr as usize] = arg;
+                self.addr = u8::wrapping_add(self.addr, 1);
+            }
+            0x4 => self.addr = (self.addr & 0xF0) | arg,
+            0x5 => self.addr = (self.addr & 0x0F) | (arg << 4),
+            0x6 => match arg {
+                0x0 => self.copy_time_to_mem(),
+                0x1 => self.copy_mem_to_time(),
+                0x2 => self.response = 0x01, // status: always ready
+                _ => {}                      // tone generator and others are not emulated
+            },
+            _ => {}
+        }
+        self.last_cmd = val;
+    }
+
+    fn copy_time_to_mem(&mut self) {
+        for i in 0..3 {
+            self.mem[i] = ((self.minutes >> (i * 4)) & 0x0F) as u8;
+        }
+        for i in 0..4 {
+            self.mem[i + 3] = ((self.days >> (i * 4)) & 0x0F) as u8;
+        }
+    }
+
+    fn copy_mem_to_time(&mut self) {
+        self.minutes = 0;
+        for i in 0..3 {
+            self.minutes |= ((self.mem[i] & 0x0F) as u16) << (i * 4);
+        }
+        self.days = 0;
+        for i in 0..4 {
+            self.days |= ((self.mem[i + 3] & 0x0F) as u16) << (i * 4);
+        }
+    }
+}
+
+impl CartridgeTrait for HuC3 {
+    fn init_rom_banks(&mut self, nbanks: u16, raw_rom: &Vec<u8>) -> Result<(), CartridgeError> {
+        if nbanks > 128 {
+            return Err(CartridgeError::InvalidCombination {
+                tp: "HuC3".to_string(),
+                feat: "more than 128 banks of ROM".to_string(),
+            });
+        }
+
+        if (nbanks as usize) * 0x4000 < raw_rom.len() {
+            return Err(CartridgeError::OutOfRomBanks { nbanks, rom_size: raw_rom.len() / 1024 });
+        }
+
+        self.rom = vec![BLANK_ROM; nbanks as usize];
+
+        for i in 0..nbanks as usize {
+            for j in 0..0x4000 {
+                self.rom[i][j] = raw_rom[(i * 0x4000) + j];
+            }
+        }
+
+        self.rom_bank_mask = (nbanks - 1) as u8;
+
+        Ok(())
+    }
+
+    fn init_ram_banks(&mut self, nbanks: u16) -> Result<(), CartridgeError> {
+        if nbanks > 4 {
+            return Err(CartridgeError::InvalidCombination {
+                tp: "HuC3".to_string(),
+                feat: "more than 4 banks of RAM".to_string(),
+            });
+        }
+        self.ram = match nbanks {
+            0 => vec![BLANK_RAM; 1], // allocate the minimum ammount to be safe
+            _ => vec![BLANK_RAM; nbanks as usize],
+        };
+
+        Ok(())
+    }
+
+    fn rom0_read(&self, addr: u16) -> u8 {
+        self.rom[0][addr as usize]
+    }
+
+    fn romx_read(&self, addr: u16) -> u8 {
+        self.rom[(self.rom_bank & self.rom_bank_mask) as usize][(addr - 0x4000) as usize]
+    }
+
+    fn sram_read(&self, addr: u16) -> u8 {
+        match self.mode {
+            0x00 | 0x0A => self.ram[self.ram_bank as usize % self.ram.len()][(addr & 0x1FFF) as usize],
+            0x0C => (self.rtc.last_cmd & 0xF0) | self.rtc.response,
+            0x0D => 0x01, // commands execute immediately, so the RTC is always ready
+            0x0E => 0xC0, // there is no IR peer, so the sensor never sees any light
+            _ => 0xFF,
+        }
+    }
+
+    fn rom0_write(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.mode = val & 0x0F,
+            0x2000..=0x3FFF => self.rom_bank = val & 0x7F,
+            _ => panic!(),
+        }
+    }
+
+    fn romx_write(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x4000..=0x5FFF => self.ram_bank = val & 0x03,
+            0x6000..=0x7FFF => {}
+            _ => panic!(),
+        }
+    }
+
+    fn sram_write(&mut self, addr: u16, val: u8) {
+        match self.mode {
+            // mode 0 maps RAM read-only
+            0x0A => {
+                let nbanks = self.ram.len();
+                self.ram[self.ram_bank as usize % nbanks][(addr & 0x1FFF) as usize] = val
+            }
+            0x0B => self.rtc.command(val),
+            _ => {}
+        }
+    }
+
+    fn cycle(&mut self, cycles: u8) {
+        self.rtc.tick(cycles);
+    }
+
+    // RAM, then minutes, days and when it was saved in seconds since the epoch
+    fn battery(&self) -> Option<Vec<u8>> {
+        let mut data: Vec<u8> = self.ram.iter().flatten().copied().collect();
+        data.extend_from_slice(&self.rtc.minutes.to_le_bytes());
+        data.extend_from_slice(&self.rtc.days.to_le_bytes());
+        data.extend_from_slice(&unix_time().to_le_bytes());
+        Some(data)
+    }
+
+    fn load_battery(&mut self, data: &[u8]) {
+        let ram_size = self.ram.len() * 0x2000;
+        if data.len() != ram_size + 12 {
+            println!("HuC3 save has the wrong size, ignoring it");
+            return;
+        }
+
+        for (bank, saved) in self.ram.iter_mut().zip(data[..ram_size].chunks_exact(0x2000)) {
+            bank.copy_from_slice(saved);
+        }
+        let rtc = &data[ram_size..];
+        self.rtc.minutes = u16::from_le_bytes([rtc[0], rtc[1]]);
+        self.rtc.days = u16::from_le_bytes([rtc[2], rtc[3]]);
+        let saved_at = u64::from_le_bytes(rtc[4..12].try_into().unwrap());
+        self.rtc.advance(unix_time().saturating_sub(saved_at) / 60);
+    }
+
+    fn mapped_bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => 0,
+            0x4000..=0x7FFF => (self.rom_bank & self.rom_bank_mask) as u16,
+            _ => match self.mode {
+                0x00 | 0x0A => (self.ram_bank as usize % self.ram.len().max(1)) as u16,
+                _ => NO_BANK,
+            },
+        }
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs())
+}
@@ -1,4 +1,4 @@
-use crate::mmu::cart::{CartridgeError, CartridgeTrait, RamBank, RomBank, BLANK_RAM, BLANK_ROM};
+use crate::mmu::cart::{CartridgeError, CartridgeTrait, RamBank, RomBank, BLANK_RAM, BLANK_ROM, NO_BANK};
 
 pub struct Mbc1 {
     rom: Vec<RomBank>,
@@ -9,6 +9,10 @@ pub struct Mbc1 {
     mask: u8,
     bank_lo: u8,
     bank_hi: u8,
+
+    // MBC1M multicarts wire bank_hi to bits 4-5 of the ROM bank instead of 5-6,
+    // leaving bit 4 of bank_lo disconnected
+    multicart: bool,
 }
 
 enum Ram {
@@ -19,13 +23,57 @@ enum Ram {
 impl Mbc1 {
     pub fn init(ram: bool) -> Self {
         if ram {
-            Self { rom: vec![], ram: Ram::RAM(vec![]), mode: 0, ram_enable: false, mask: 0, bank_lo: 1, bank_hi: 0 }
+            Self {
+                rom: vec![],
+                ram: Ram::RAM(vec![]),
+                mode: 0,
+                ram_enable: false,
+                mask: 0,
+                bank_lo: 1,
+                bank_hi: 0,
+                multicart: false,
+            }
         } else {
-            Self { rom: vec![], ram: Ram::NONE, mode: 0, ram_enable: false, mask: 0, bank_lo: 1, bank_hi: 0 }
+            Self {
+                rom: vec![],
+                ram: Ram::NONE,
+                mode: 0,
+                ram_enable: false,
+                mask: 0,
+                bank_lo: 1,
+                bank_hi: 0,
+                multicart: false,
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn bank_hi_shift(&self) -> u8 {
+        match self.multicart {
+            false => 5,
+            true => 4,
+        }
+    }
+
+    #[inline(always)]
+    fn bank_lo_mask(&self) -> u8 {
+        match self.multicart {
+            false => 0b00011111,
+            true => 0b00001111,
         }
     }
 }
 
+// Multicarts are 8Mbit carts that hold several games, each one starting with
+// its own header. They are detected by looking for a second Nintendo logo at
+// the start of the game located in bank $10.
+fn is_multicart(nbanks: u16, raw_rom: &[u8]) -> bool {
+    if nbanks != 64 || raw_rom.len() < 0x40134 {
+        return false;
+    }
+    raw_rom[0x0104..0x0134] == raw_rom[0x40104..0x40134]
+}
+
 impl CartridgeTrait for Mbc1 {
     fn init_rom_banks(&mut self, nbanks: u16, raw_rom: &Vec<u8>) -> Result<(), CartridgeError> {
         if nbanks > 128 {
@@ -50,6 +98,8 @@ impl CartridgeTrait for Mbc1 {
             }
         }
 
+        self.multicart = is_multicart(nbanks, raw_rom);
+
         Ok(())
     }
 
@@ -81,7 +131,7 @@ impl CartridgeTrait for Mbc1 {
     }
 
     fn rom0_read(&self, addr: u16) -> u8 {
-        let rom_bank = (self.bank_hi << 5) as usize;
+        let rom_bank = (self.bank_hi << self.bank_hi_shift()) as usize;
         match (self.mode, self.rom.len() > rom_bank) {
             (0, _) | (1, false) => self.rom[0][addr as usize],
             (1, true) => self.rom[rom_bank][addr as usize],
@@ -90,7 +140,7 @@ impl CartridgeTrait for Mbc1 {
     }
 
     fn romx_read(&self, addr: u16) -> u8 {
-        let rom_bank = (self.bank_hi << 5) + self.bank_lo;
+        let rom_bank = (self.bank_hi << self.bank_hi_shift()) + (self.bank_lo & self.bank_lo_mask());
         self.rom[(rom_bank & self.mask) as usize][(addr - 0x4000) as usize]
     }
 
@@ -143,4 +193,16 @@ impl CartridgeTrait for Mbc1 {
             }
         }
     }
+
+    fn mapped_bank(&self, addr: u16) -> u16 {
+        let bank_hi = (self.bank_hi << self.bank_hi_shift()) as u16;
+        match (addr, self.mode) {
+            (0x0000..=0x3FFF, 1) if self.rom.len() > bank_hi as usize => bank_hi,
+            (0x0000..=0x3FFF, _) => 0,
+            (0x4000..=0x7FFF, _) => (bank_hi + (self.bank_lo & self.bank_lo_mask()) as u16) & self.mask as u16,
+            _ if !self.ram_enable || matches!(self.ram, Ram::NONE) => NO_BANK,
+            (_, 1) => self.bank_hi as u16,
+            _ => 0,
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use crate::mmu::cart::{CartridgeError, CartridgeTrait, RomBank, BLANK_ROM};
+use crate::mmu::cart::{CartridgeError, CartridgeTrait, NO_BANK, RomBank, BLANK_ROM};
 
 pub struct Mbc2 {
     rom: Vec<RomBank>,
@@ -84,4 +84,13 @@ impl CartridgeTrait for Mbc2 {
             self.ram[(addr & 0x01FF) as usize] = val;
         }
     }
+
+    fn mapped_bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => 0,
+            0x4000..=0x7FFF => (self.bank & self.bank_mask) as u16,
+            _ if !self.ram_enable => NO_BANK,
+            _ => 0,
+        }
+    }
 }
@@ -1,6 +1,6 @@
 // TODO: Implement the MBC30 variant. Required if we want to play Pokémon Crystal.
 // We want to play Pokémon Crystal.
-use crate::mmu::cart::{CartridgeError, CartridgeTrait, RamBank, RomBank, BLANK_RAM, BLANK_ROM};
+use crate::mmu::cart::{CartridgeError, CartridgeTrait, RamBank, RomBank, BLANK_RAM, BLANK_ROM, NO_BANK};
 
 pub struct Mbc3 {
     rom: Vec<RomBank>,
@@ -216,4 +216,17 @@ impl CartridgeTrait for Mbc3 {
             }
         }
     }
+
+    fn mapped_bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => 0,
+            0x4000..=0x7FFF => self.rom_bank as u16,
+            _ if !self.ram_enable => NO_BANK,
+            _ => match (&self.extras, self.ram_rtc_sel) {
+                (Extras::Ram(_), sel) => (sel & 0x03) as u16,
+                (Extras::RamTimer(_), sel @ 0x00..=0x03) => sel as u16,
+                _ => NO_BANK,
+            },
+        }
+    }
 }
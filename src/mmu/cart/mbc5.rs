@@ -1,4 +1,4 @@
-use crate::mmu::cart::{CartridgeError, CartridgeTrait, RamBank, RomBank, BLANK_RAM, BLANK_ROM};
+use crate::mmu::cart::{CartridgeError, CartridgeTrait, RamBank, RomBank, BLANK_RAM, BLANK_ROM, NO_BANK};
 
 pub struct Mbc5 {
     rom: Vec<RomBank>,
@@ -9,6 +9,7 @@ pub struct Mbc5 {
     rom_bank_mask: u16,
     ram_bank: u8,
     ram_enable: bool,
+    rumble: bool,
 }
 
 enum Extras {
@@ -26,7 +27,16 @@ impl Mbc5 {
             (false, true) => Extras::Rumble,
             (true, true) => Extras::RamRumble(vec![]),
         };
-        Self { rom: vec![], extras, rom_bank_lo: 1, rom_bank_hi: 0, rom_bank_mask: 0, ram_bank: 0, ram_enable: false }
+        Self {
+            rom: vec![],
+            extras,
+            rom_bank_lo: 1,
+            rom_bank_hi: 0,
+            rom_bank_mask: 0,
+            ram_bank: 0,
+            ram_enable: false,
+            rumble: false,
+        }
     }
 }
 
@@ -113,15 +123,13 @@ impl CartridgeTrait for Mbc5 {
 
     fn romx_write(&mut self, addr: u16, val: u8) {
         match addr {
-            0x4000..=0x5FFF => {
-                match self.extras {
-                    Extras::None | Extras::Ram(_) => self.ram_bank = val & 0x0F,
-                    Extras::Rumble | Extras::RamRumble(_) => {
-                        self.ram_bank = (val & 0x0F) & !0x08
-                        // TODO: set rumble
-                    }
+            0x4000..=0x5FFF => match self.extras {
+                Extras::None | Extras::Ram(_) => self.ram_bank = val & 0x0F,
+                Extras::Rumble | Extras::RamRumble(_) => {
+                    self.ram_bank = (val & 0x0F) & !0x08;
+                    self.rumble = val & 0x08 != 0;
                 }
-            }
+            },
             _ => {}
         }
     }
@@ -136,4 +144,19 @@ impl CartridgeTrait for Mbc5 {
             }
         }
     }
+
+    fn mapped_bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => 0,
+            0x4000..=0x7FFF => (((self.rom_bank_hi as u16) << 8) | (self.rom_bank_lo as u16)) & self.rom_bank_mask,
+            _ => match self.extras {
+                Extras::Ram(_) | Extras::RamRumble(_) if self.ram_enable => self.ram_bank as u16,
+                _ => NO_BANK,
+            },
+        }
+    }
+
+    fn rumble(&self) -> bool {
+        self.rumble
+    }
 }
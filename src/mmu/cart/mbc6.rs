@@ -0,0 +1,250 @@
+use crate::mmu::cart::{CartridgeError, CartridgeTrait, RamBank, RomBank, BLANK_RAM, BLANK_ROM, NO_BANK};
+
+const FLASH_SIZE: usize = 0x100000;
+const FLASH_SECTOR_SIZE: usize = 0x20000;
+
+pub struct Mbc6 {
+    rom: Vec<RomBank>,
+    ram: Vec<RamBank>,
+    flash: Flash,
+
+    // ROM and RAM are both split into two independent windows:
+    // $4000-$5FFF/$A000-$AFFF (A) and $6000-$7FFF/$B000-$BFFF (B)
+    rom_bank: [u8; 2],
+    rom_bank_mask: u8,
+    flash_sel: [bool; 2],
+    ram_bank: [u8; 2],
+    ram_enable: bool,
+}
+
+// The MX29F008 flash chip follows the usual AMD command protocol, where every
+// command is preceded by an unlock sequence written to fixed addresses.
+struct Flash {
+    bytes: Vec<u8>,
+    enable: bool,
+    write_enable: bool,
+    state: FlashState,
+}
+
+#[derive(Clone, Copy)]
+enum FlashState {
+    READ,
+    UNLOCK1,
+    UNLOCK2,
+    PROGRAM,
+    ERASESETUP,
+    ERASEUNLOCK1,
+    ERASEUNLOCK2,
+    ID,
+}
+
+impl Mbc6 {
+    pub fn init() -> Self {
+        Self {
+            rom: vec![],
+            ram: vec![],
+            flash: Flash { bytes: vec![0xFF; FLASH_SIZE], enable: false, write_enable: false, state: FlashState::READ },
+            rom_bank: [0; 2],
+            rom_bank_mask: 0,
+            flash_sel: [false; 2],
+            ram_bank: [0; 2],
+            ram_enable: false,
+        }
+    }
+
+    #[inline(always)]
+    fn rom_window(addr: u16) -> usize {
+        ((addr >> 13) & 0x01) as usize
+    }
+
+    #[inline(always)]
+    fn ram_window(addr: u16) -> usize {
+        ((addr >> 12) & 0x01) as usize
+    }
+
+    #[inline(always)]
+    fn flash_addr(&self, addr: u16) -> usize {
+        let bank = self.rom_bank[Mbc6::rom_window(addr)] as usize;
+        (bank * 0x2000 + (addr & 0x1FFF) as usize) % FLASH_SIZE
+    }
+}
+
+impl Flash {
+    fn read(&self, flash_addr: usize) -> u8 {
+        match self.state {
+            // manufacturer and device IDs for the MX29F008
+            FlashState::ID => match flash_addr & 0x01 {
+                0 => 0xC2,
+                _ => 0x81,
+            },
+            _ => self.bytes[flash_addr],
+        }
+    }
+
+    fn write(&mut self, flash_addr: usize, val: u8) {
+        if !self.write_enable {
+            return;
+        }
+
+        let cmd_addr = flash_addr & 0x7FFF;
+        self.state = match (self.state, cmd_addr, val) {
+            // data bytes and erase confirmations come before the reset command,
+            // so programming $F0 writes it instead of resetting the chip
+            (FlashState::PROGRAM, _, _) => {
+                // programming can only clear bits, erasing is needed to set them
+                self.bytes[flash_addr] &= val;
+                FlashState::READ
+            }
+            (FlashState::ERASEUNLOCK2, _, 0x30) => {
+                let sector = flash_addr & !(FLASH_SECTOR_SIZE - 1);
+                self.bytes[sector..sector + FLASH_SECTOR_SIZE].fill(0xFF);
+                FlashState::READ
+            }
+            (FlashState::ERASEUNLOCK2, 0x5555, 0x10) => {
+                self.bytes.fill(0xFF);
+                FlashState::READ
+            }
+            (_, _, 0xF0) => FlashState::READ,
+            (FlashState::READ | FlashState::ID, 0x5555, 0xAA) => FlashState::UNLOCK1,
+            (FlashState::UNLOCK1, 0x2AAA, 0x55) => FlashState::UNLOCK2,
+            (FlashState::UNLOCK2, 0x5555, 0xA0) => FlashState::PROGRAM,
+            (FlashState::UNLOCK2, 0x5555, 0x80) => FlashState::ERASESETUP,
+            (FlashState::UNLOCK2, 0x5555, 0x90) => FlashState::ID,
+            (FlashState::ERASESETUP, 0x5555, 0xAA) => FlashState::ERASEUNLOCK1,
+            (FlashState::ERASEUNLOCK1, 0x2AAA, 0x55) => FlashState::ERASEUNLOCK2,
+            (FlashState::ID, _, _) => FlashState::ID,
+            _ => FlashState::READ,
+        };
+    }
+}
+
+impl CartridgeTrait for Mbc6 {
+    fn init_rom_banks(&mut self, nbanks: u16, raw_rom: &Vec<u8>) -> Result<(), CartridgeError> {
+        if nbanks > 64 {
+            return Err(CartridgeError::InvalidCombination {
+                tp: "MBC6".to_string(),
+                feat: "more than 64 banks of ROM".to_string(),
+            });
+        }
+
+        if (nbanks as usize) * 0x4000 < raw_rom.len() {
+            return Err(CartridgeError::OutOfRomBanks { nbanks, rom_size: raw_rom.len() / 1024 });
+        }
+
+        self.rom = vec![BLANK_ROM; nbanks as usize];
+
+        for i in 0..nbanks as usize {
+            for j in 0..0x4000 {
+                self.rom[i][j] = raw_rom[(i * 0x4000) + j];
+            }
+        }
+
+        // banks are switched in 8KiB halves
+        self.rom_bank_mask = ((nbanks * 2) - 1) as u8;
+
+        Ok(())
+    }
+
+    fn init_ram_banks(&mut self, nbanks: u16) -> Result<(), CartridgeError> {
+        if nbanks > 4 {
+            return Err(CartridgeError::InvalidCombination {
+                tp: "MBC6".to_string(),
+                feat: "more than 4 banks of RAM".to_string(),
+            });
+        }
+        self.ram = match nbanks {
+            0 => vec![BLANK_RAM; 1], // allocate the minimum ammount to be safe
+            _ => vec![BLANK_RAM; nbanks as usize],
+        };
+
+        Ok(())
+    }
+
+    fn rom0_read(&self, addr: u16) -> u8 {
+        self.rom[0][addr as usize]
+    }
+
+    fn romx_read(&self, addr: u16) -> u8 {
+        let window = Mbc6::rom_window(addr);
+        match (self.flash_sel[window], self.flash.enable) {
+            (true, true) => self.flash.read(self.flash_addr(addr)),
+            (true, false) => 0xFF,
+            (false, _) => {
+                let half_bank = (self.rom_bank[window] & self.rom_bank_mask) as usize;
+                self.rom[half_bank / 2][(half_bank % 2) * 0x2000 + (addr & 0x1FFF) as usize]
+            }
+        }
+    }
+
+    fn sram_read(&self, addr: u16) -> u8 {
+        if self.ram_enable {
+            // RAM is switched in 4KiB halves
+            let ram_addr = self.ram_bank[Mbc6::ram_window(addr)] as usize * 0x1000 + (addr & 0x0FFF) as usize;
+            let bank = (ram_addr / 0x2000) % self.ram.len();
+            return self.ram[bank][ram_addr % 0x2000];
+        }
+
+        0xFF
+    }
+
+    fn rom0_write(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x0000..=0x03FF => self.ram_enable = (val & 0x0F) == 0x0A,
+            0x0400..=0x07FF => self.ram_bank[0] = val & 0x07,
+            0x0800..=0x0BFF => self.ram_bank[1] = val & 0x07,
+            0x0C00..=0x0FFF => self.flash.enable = val & 0x01 != 0,
+            0x1000 => self.flash.write_enable = val & 0x01 != 0,
+            0x1001..=0x1FFF => {}
+            0x2000..=0x27FF => self.rom_bank[0] = val & 0x7F,
+            0x2800..=0x2FFF => self.flash_sel[0] = val == 0x08,
+            0x3000..=0x37FF => self.rom_bank[1] = val & 0x7F,
+            0x3800..=0x3FFF => self.flash_sel[1] = val == 0x08,
+            _ => panic!(),
+        }
+    }
+
+    fn romx_write(&mut self, addr: u16, val: u8) {
+        if self.flash_sel[Mbc6::rom_window(addr)] && self.flash.enable {
+            let flash_addr = self.flash_addr(addr);
+            self.flash.write(flash_addr, val);
+        }
+    }
+
+    fn sram_write(&mut self, addr: u16, val: u8) {
+        if self.ram_enable {
+            let ram_addr = self.ram_bank[Mbc6::ram_window(addr)] as usize * 0x1000 + (addr & 0x0FFF) as usize;
+            let bank = (ram_addr / 0x2000) % self.ram.len();
+            self.ram[bank][ram_addr % 0x2000] = val;
+        }
+    }
+
+    // MBC6 switches 8KiB halves, this gives the 16KiB bank holding the half
+    // RAM followed by the whole flash chip
+    fn battery(&self) -> Option<Vec<u8>> {
+        let mut data: Vec<u8> = self.ram.iter().flatten().copied().collect();
+        data.extend_from_slice(&self.flash.bytes);
+        Some(data)
+    }
+
+    fn load_battery(&mut self, data: &[u8]) {
+        let ram_size = self.ram.len() * 0x2000;
+        if data.len() != ram_size + FLASH_SIZE {
+            println!("MBC6 save has the wrong size, ignoring it");
+            return;
+        }
+
+        for (bank, saved) in self.ram.iter_mut().zip(data[..ram_size].chunks_exact(0x2000)) {
+            bank.copy_from_slice(saved);
+        }
+        self.flash.bytes.copy_from_slice(&data[ram_size..]);
+    }
+
+    fn mapped_bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => 0,
+            0x4000..=0x7FFF => (self.rom_bank[Mbc6::rom_window(addr)] & self.rom_bank_mask) as u16 / 2,
+            _ if !self.ram_enable || self.ram.is_empty() => NO_BANK,
+            _ => (self.ram_bank[Mbc6::ram_window(addr)] as usize / 2 % self.ram.len()) as u16,
+        }
+    }
+}
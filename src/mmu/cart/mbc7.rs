@@ -0,0 +1,320 @@
+use crate::mmu::cart::{CartridgeError, CartridgeTrait, RomBank, BLANK_ROM, NO_BANK};
+
+// accelerometer readings are centered around this value, and 1g of tilt moves
+// them by roughly ACCEL_ONE_G in either direction
+const ACCEL_CENTER: u16 = 0x81D0;
+const ACCEL_ONE_G: f32 = 112.0;
+
+pub struct Mbc7 {
+    rom: Vec<RomBank>,
+    eeprom: Eeprom,
+
+    rom_bank: u8,
+    rom_bank_mask: u8,
+    ram_enable1: bool,
+    ram_enable2: bool,
+
+    // current tilt in g, as reported by the frontend
+    tilt_x: f32,
+    tilt_y: f32,
+    latch_x: u16,
+    latch_y: u16,
+    latch_ready: bool,
+}
+
+// 93LC56 serial EEPROM, organized as 128 16-bit words. It is bit-banged
+// through a single register holding its CS, CLK, DI and DO lines.
+struct Eeprom {
+    words: [u16; 128],
+
+    cs: bool,
+    clk: bool,
+    di: bool,
+    dout: bool,
+
+    write_enable: bool,
+    shift_in: u32,
+    bits_in: u8,
+    shift_out: u16,
+    state: EepromState,
+}
+
+#[derive(Clone, Copy)]
+enum EepromState {
+    IDLE,
+    COMMAND,
+    READ,
+    WRITE(u8),
+    WRITEALL,
+    DONE,
+}
+
+impl Mbc7 {
+    pub fn init() -> Self {
+        Self {
+            rom: vec![],
+            eeprom: Eeprom {
+                words: [0xFFFF; 128],
+                cs: false,
+                clk: false,
+                di: false,
+                dout: true,
+                write_enable: false,
+                shift_in: 0,
+                bits_in: 0,
+                shift_out: 0,
+                state: EepromState::IDLE,
+            },
+            rom_bank: 1,
+            rom_bank_mask: 0,
+            ram_enable1: false,
+            ram_enable2: false,
+            tilt_x: 0.0,
+            tilt_y: 0.0,
+            latch_x: 0x8000,
+            latch_y: 0x8000,
+            latch_ready: false,
+        }
+    }
+
+    #[inline(always)]
+    fn ram_enabled(&self) -> bool {
+        self.ram_enable1 && self.ram_enable2
+    }
+
+    #[inline(always)]
+    fn accel_value(tilt: f32) -> u16 {
+        let offset = (tilt.clamp(-2.0, 2.0) * ACCEL_ONE_G) as i16;
+        u16::wrapping_add(ACCEL_CENTER, offset as u16)
+    }
+}
+
+impl Eeprom {
+    fn read(&self) -> u8 {
+        ((self.cs as u8) << 7) | ((self.clk as u8) << 6) | ((self.di as u8) << 1) | self.dout as u8
+    }
+
+    fn write(&mut self, val: u8) {
+        let cs = val & 0x80 != 0;
+        let clk = val & 0x40 != 0;
+        self.di = val & 0x02 != 0;
+
+        if !cs {
+            self.state = EepromState::IDLE;
+        } else if !self.cs {
+            // selecting the chip reports the result of the last operation, which is always ready
+            self.dout = true;
+            self.state = EepromState::COMMAND;
+            self.shift_in = 0;
+            self.bits_in = 0;
+        } else if !self.clk && clk {
+            self.clock();
+        }
+
+        self.cs = cs;
+        self.clk = clk;
+    }
+
+    fn clock(&mut self) {
+        match self.state {
+            EepromState::IDLE | EepromState::DONE => {}
+            EepromState::COMMAND => {
+                // leading zeroes before the start bit are ignored
+                if self.bits_in == 0 && !self.di {
+                    return;
+                }
+                self.shift_bit();
+                // start bit + 2 bit opcode + 8 bit address
+                if self.bits_in == 11 {
+                    self.decode();
+                }
+            }
+            EepromState::READ => {
+                self.dout = self.shift_out & 0x8000 != 0;
+                self.shift_out <<= 1;
+            }
+            EepromState::WRITE(addr) => {
+                self.shift_bit();
+                if self.bits_in == 27 {
+                    if self.write_enable {
+                        self.words[addr as usize] = self.shift_in as u16;
+                    }
+                    self.state = EepromState::DONE;
+                }
+            }
+            EepromState::WRITEALL => {
+                self.shift_bit();
+                if self.bits_in == 27 {
+                    if self.write_enable {
+                        self.words = [self.shift_in as u16; 128];
+                    }
+                    self.state = EepromState::DONE;
+                }
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn shift_bit(&mut self) {
+        self.shift_in = (self.shift_in << 1) | self.di as u32;
+        self.bits_in += 1;
+    }
+
+    fn decode(&mut self) {
+        let addr = (self.shift_in & 0x7F) as u8;
+        self.state = match (self.shift_in >> 8) & 0x03 {
+            0b10 => {
+                // a dummy zero bit is output before the data
+                self.dout = false;
+                self.shift_out = self.words[addr as usize];
+                EepromState::READ
+            }
+            0b01 => EepromState::WRITE(addr),
+            0b11 => {
+                if self.write_enable {
+                    self.words[addr as usize] = 0xFFFF;
+                }
+                EepromState::DONE
+            }
+            _ => match (self.shift_in >> 6) & 0x03 {
+                0b00 => {
+                    self.write_enable = false;
+                    EepromState::DONE
+                }
+                0b01 => EepromState::WRITEALL,
+                0b10 => {
+                    if self.write_enable {
+                        self.words = [0xFFFF; 128];
+                    }
+                    EepromState::DONE
+                }
+                _ => {
+                    self.write_enable = true;
+                    EepromState::DONE
+                }
+            },
+        };
+    }
+}
+
+impl CartridgeTrait for Mbc7 {
+    fn init_rom_banks(&mut self, nbanks: u16, raw_rom: &Vec<u8>) -> Result<(), CartridgeError> {
+        if nbanks > 128 {
+            return Err(CartridgeError::InvalidCombination {
+                tp: "MBC7".to_string(),
+                feat: "more than 128 banks of ROM".to_string(),
+            });
+        }
+
+        if (nbanks as usize) * 0x4000 < raw_rom.len() {
+            return Err(CartridgeError::OutOfRomBanks { nbanks, rom_size: raw_rom.len() / 1024 });
+        }
+
+        self.rom = vec![BLANK_ROM; nbanks as usize];
+
+        for i in 0..nbanks as usize {
+            for j in 0..0x4000 {
+                self.rom[i][j] = raw_rom[(i * 0x4000) + j];
+            }
+        }
+
+        self.rom_bank_mask = (nbanks - 1) as u8;
+
+        Ok(())
+    }
+
+    fn init_ram_banks(&mut self, _nbanks: u16) -> Result<(), CartridgeError> {
+        // the EEPROM is not described by the header, so whatever it says is ignored
+        Ok(())
+    }
+
+    fn rom0_read(&self, addr: u16) -> u8 {
+        self.rom[0][addr as usize]
+    }
+
+    fn romx_read(&self, addr: u16) -> u8 {
+        self.rom[(self.rom_bank & self.rom_bank_mask) as usize][(addr - 0x4000) as usize]
+    }
+
+    fn sram_read(&self, addr: u16) -> u8 {
+        if !self.ram_enabled() || addr >= 0xB000 {
+            return 0xFF;
+        }
+
+        match (addr >> 4) & 0x0F {
+            0x2 => self.latch_x as u8,
+            0x3 => (self.latch_x >> 8) as u8,
+            0x4 => self.latch_y as u8,
+            0x5 => (self.latch_y >> 8) as u8,
+            0x6 => 0x00,
+            0x8 => self.eeprom.read(),
+            _ => 0xFF,
+        }
+    }
+
+    fn rom0_write(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enable1 = val == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = val & 0x7F,
+            _ => panic!(),
+        }
+    }
+
+    fn romx_write(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x4000..=0x5FFF => self.ram_enable2 = val == 0x40,
+            0x6000..=0x7FFF => {}
+            _ => panic!(),
+        }
+    }
+
+    fn sram_write(&mut self, addr: u16, val: u8) {
+        if !self.ram_enabled() || addr >= 0xB000 {
+            return;
+        }
+
+        match ((addr >> 4) & 0x0F, val) {
+            (0x0, 0x55) => {
+                self.latch_x = 0x8000;
+                self.latch_y = 0x8000;
+                self.latch_ready = true;
+            }
+            (0x1, 0xAA) if self.latch_ready => {
+                self.latch_x = Mbc7::accel_value(self.tilt_x);
+                self.latch_y = Mbc7::accel_value(self.tilt_y);
+                self.latch_ready = false;
+            }
+            (0x8, _) => self.eeprom.write(val),
+            _ => {}
+        }
+    }
+
+    // the EEPROM's words, little endian
+    fn battery(&self) -> Option<Vec<u8>> {
+        Some(self.eeprom.words.iter().flat_map(|word| word.to_le_bytes()).collect())
+    }
+
+    fn load_battery(&mut self, data: &[u8]) {
+        if data.len() != self.eeprom.words.len() * 2 {
+            println!("MBC7 save has the wrong size, ignoring it");
+            return;
+        }
+
+        for (word, saved) in self.eeprom.words.iter_mut().zip(data.chunks_exact(2)) {
+            *word = u16::from_le_bytes([saved[0], saved[1]]);
+        }
+    }
+
+    fn mapped_bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => 0,
+            0x4000..=0x7FFF => (self.rom_bank & self.rom_bank_mask) as u16,
+            _ => NO_BANK,
+        }
+    }
+
+    fn set_accelerometer(&mut self, x: f32, y: f32) {
+        self.tilt_x = x;
+        self.tilt_y = y;
+    }
+}
@@ -0,0 +1,222 @@
+use crate::mmu::cart::{CartridgeError, CartridgeTrait, RamBank, RomBank, BLANK_RAM, BLANK_ROM, NO_BANK};
+
+pub struct Mmm01 {
+    rom: Vec<RomBank>,
+    ram: Ram,
+
+    // The cartridge boots unmapped, with the menu in the last 32KiB of ROM
+    // visible. The menu then sets up the upper bank bits and masks for the
+    // chosen game and locks them by entering mapped mode, after which the game
+    // sees a regular MBC1 that can only change the unmasked bits.
+    mapped: bool,
+    ram_enable: bool,
+    rom_bank_lo: u8,
+    rom_bank_mid: u8,
+    rom_bank_hi: u8,
+    rom_mask: u8,
+    ram_bank_lo: u8,
+    ram_bank_hi: u8,
+    ram_mask: u8,
+    mode: u8,
+    mode_lock: bool,
+}
+
+enum Ram {
+    NONE,
+    RAM(Vec<RamBank>),
+}
+
+impl Mmm01 {
+    pub fn init(ram: bool) -> Self {
+        Self {
+            rom: vec![],
+            ram: match ram {
+                true => Ram::RAM(vec![]),
+                false => Ram::NONE,
+            },
+            mapped: false,
+            ram_enable: false,
+            rom_bank_lo: 0,
+            rom_bank_mid: 0,
+            rom_bank_hi: 0,
+            rom_mask: 0,
+            ram_bank_lo: 0,
+            ram_bank_hi: 0,
+            ram_mask: 0,
+            mode: 0,
+            mode_lock: false,
+        }
+    }
+
+    #[inline(always)]
+    fn rom_fixed_bits(&self) -> u8 {
+        self.rom_mask << 1
+    }
+
+    fn rom0_bank(&self) -> usize {
+        if !self.mapped {
+            return self.rom.len() - 2;
+        }
+        let lo = self.rom_bank_lo & self.rom_fixed_bits();
+        (((self.rom_bank_hi as usize) << 7) | ((self.rom_bank_mid as usize) << 5) | lo as usize) % self.rom.len()
+    }
+
+    fn romx_bank(&self) -> usize {
+        if !self.mapped {
+            return self.rom.len() - 1;
+        }
+        let mut lo = self.rom_bank_lo;
+        // like in MBC1, bank 0 can't be mapped here, but only the game's bits are checked
+        if lo & !self.rom_fixed_bits() & 0x1F == 0 {
+            lo |= 1;
+        }
+        (((self.rom_bank_hi as usize) << 7) | ((self.rom_bank_mid as usize) << 5) | lo as usize) % self.rom.len()
+    }
+
+    fn ram_bank(&self) -> usize {
+        let lo = match self.mode {
+            0 => self.ram_bank_lo & self.ram_mask,
+            _ => self.ram_bank_lo,
+        };
+        ((self.ram_bank_hi << 2) | lo) as usize
+    }
+}
+
+impl CartridgeTrait for Mmm01 {
+    fn init_rom_banks(&mut self, nbanks: u16, raw_rom: &Vec<u8>) -> Result<(), CartridgeError> {
+        if nbanks > 512 {
+            return Err(CartridgeError::InvalidCombination {
+                tp: "MMM01".to_string(),
+                feat: "more than 512 banks of ROM".to_string(),
+            });
+        }
+
+        if (nbanks as usize) * 0x4000 < raw_rom.len() {
+            return Err(CartridgeError::OutOfRomBanks { nbanks, rom_size: raw_rom.len() / 1024 });
+        }
+
+        self.rom = vec![BLANK_ROM; nbanks as usize];
+
+        for i in 0..nbanks as usize {
+            for j in 0..0x4000 {
+                self.rom[i][j] = raw_rom[(i * 0x4000) + j];
+            }
+        }
+
+        Ok(())
+    }
+
+    fn init_ram_banks(&mut self, nbanks: u16) -> Result<(), CartridgeError> {
+        match self.ram {
+            Ram::NONE => {
+                if nbanks != 0 {
+                    return Err(CartridgeError::InvalidCombination {
+                        tp: "MMM01 without RAM".to_string(),
+                        feat: "RAM banks".to_string(),
+                    });
+                }
+            }
+            Ram::RAM(ref mut ram) => {
+                if nbanks > 16 {
+                    return Err(CartridgeError::InvalidCombination {
+                        tp: "MMM01 with RAM".to_string(),
+                        feat: "more than 16 banks of RAM".to_string(),
+                    });
+                }
+                *ram = match nbanks {
+                    0 => vec![BLANK_RAM; 1], // allocate the minimum ammount to be safe
+                    _ => vec![BLANK_RAM; nbanks as usize],
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn rom0_read(&self, addr: u16) -> u8 {
+        self.rom[self.rom0_bank()][addr as usize]
+    }
+
+    fn romx_read(&self, addr: u16) -> u8 {
+        self.rom[self.romx_bank()][(addr - 0x4000) as usize]
+    }
+
+    fn sram_read(&self, addr: u16) -> u8 {
+        if self.ram_enable {
+            match self.ram {
+                Ram::NONE => return 0xFF,
+                Ram::RAM(ref ram) => return ram[self.ram_bank() % ram.len()][(addr - 0xA000) as usize],
+            }
+        }
+
+        0xFF
+    }
+
+    fn rom0_write(&mut self, addr: u16, val: u8) {
+        match (addr, self.mapped) {
+            (0x0000..=0x1FFF, false) => {
+                self.ram_enable = (val & 0x0F) == 0x0A;
+                self.ram_mask = (val >> 4) & 0x03;
+                self.mapped = val & 0x40 != 0;
+            }
+            (0x0000..=0x1FFF, true) => self.ram_enable = (val & 0x0F) == 0x0A,
+            (0x2000..=0x3FFF, false) => {
+                self.rom_bank_lo = val & 0x1F;
+                self.rom_bank_mid = (val >> 5) & 0x03;
+            }
+            (0x2000..=0x3FFF, true) => {
+                let fixed = self.rom_fixed_bits();
+                self.rom_bank_lo = (self.rom_bank_lo & fixed) | (val & 0x1F & !fixed);
+            }
+            _ => panic!(),
+        }
+    }
+
+    fn romx_write(&mut self, addr: u16, val: u8) {
+        match (addr, self.mapped) {
+            (0x4000..=0x5FFF, false) => {
+                self.ram_bank_lo = val & 0x03;
+                self.ram_bank_hi = (val >> 2) & 0x03;
+                self.rom_bank_hi = (val >> 4) & 0x03;
+                self.mode_lock = val & 0x40 != 0;
+            }
+            (0x4000..=0x5FFF, true) => {
+                self.ram_bank_lo = (self.ram_bank_lo & self.ram_mask) | (val & 0x03 & !self.ram_mask);
+            }
+            (0x6000..=0x7FFF, _) => {
+                if !self.mapped {
+                    self.rom_mask = (val >> 2) & 0x0F;
+                }
+                if !self.mode_lock {
+                    self.mode = val & 0x01;
+                }
+            }
+            _ => panic!(),
+        }
+    }
+
+    fn sram_write(&mut self, addr: u16, val: u8) {
+        if self.ram_enable {
+            let bank = self.ram_bank();
+            match self.ram {
+                Ram::NONE => {}
+                Ram::RAM(ref mut ram) => {
+                    let nbanks = ram.len();
+                    ram[bank % nbanks][(addr - 0xA000) as usize] = val
+                }
+            }
+        }
+    }
+
+    fn mapped_bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => self.rom0_bank() as u16,
+            0x4000..=0x7FFF => self.romx_bank() as u16,
+            _ if !self.ram_enable => NO_BANK,
+            _ => match self.ram {
+                Ram::NONE => NO_BANK,
+                Ram::RAM(ref ram) => (self.ram_bank() % ram.len().max(1)) as u16,
+            },
+        }
+    }
+}
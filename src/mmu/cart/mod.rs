@@ -1,24 +1,56 @@
-use {enum_dispatch::enum_dispatch, mbc1::Mbc1, mbc2::Mbc2, mbc3::Mbc3, mbc5::Mbc5, no_mbc::NoMbc, snafu::Snafu};
+use crate::gameboy::GameBoy;
+use {
+    enum_dispatch::enum_dispatch, huc1::HuC1, huc3::HuC3, mbc1::Mbc1, mbc2::Mbc2, mbc3::Mbc3, mbc5::Mbc5, mbc6::Mbc6,
+    mbc7::Mbc7, mmm01::Mmm01, no_mbc::NoMbc, pocket_camera::PocketCamera, sachen::Sachen, snafu::Snafu,
+    wisdom_tree::WisdomTree,
+};
 
+mod huc1;
+mod huc3;
 mod mbc1;
 mod mbc2;
 mod mbc3;
 mod mbc5;
+mod mbc6;
+mod mbc7;
+mod mmm01;
 mod no_mbc;
+mod pocket_camera;
+mod sachen;
+mod wisdom_tree;
+
+pub use pocket_camera::{ImageSource, StaticImage};
 
 pub type RomBank = [u8; 0x4000];
 pub const BLANK_ROM: RomBank = [0; 0x4000];
 
 pub type RamBank = [u8; 0x2000];
 pub const BLANK_RAM: RamBank = [0; 0x2000];
+// mapped_bank of SRAM addresses showing registers (clocks, sensors) instead of RAM
+pub const NO_BANK: u16 = 0xFFFF;
+
+#[rustfmt::skip]
+const NINTENDO_LOGO: [u8; 0x30] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
 
 #[enum_dispatch]
 pub enum CartridgeEnum {
+    HuC1,
+    HuC3,
     Mbc1,
     Mbc2,
     Mbc3,
     Mbc5,
+    Mbc6,
+    Mbc7,
+    Mmm01,
     NoMbc,
+    PocketCamera,
+    Sachen,
+    WisdomTree,
 }
 
 #[derive(Snafu, Debug)]
@@ -45,6 +77,78 @@ pub trait CartridgeTrait {
     fn rom0_write(&mut self, addr: u16, val: u8);
     fn romx_write(&mut self, addr: u16, val: u8);
     fn sram_write(&mut self, addr: u16, val: u8);
+
+    // Bank currently mapped at addr, either in ROM ($0000-$7FFF) or SRAM
+    // ($A000-$BFFF), counted like the ROM and RAM are split in banks. It has to
+    // be the bank reads at addr go to, or NO_BANK when they don't reach RAM
+    fn mapped_bank(&self, addr: u16) -> u16;
+
+    // Only cartridges with an accelerometer (MBC7) care about tilt, given in g
+    fn set_accelerometer(&mut self, _x: f32, _y: f32) {}
+
+    // Only the Pocket Camera takes pictures, of whatever the source provides
+    fn set_image_source(&mut self, _source: Box<dyn ImageSource>) {}
+
+    // Only cartridges with a clock (HuC3) need to count cycles
+    fn cycle(&mut self, _cycles: u8) {}
+
+    // Battery backed state kept between sessions, for the cartridges that
+    // save it: HuC3's RAM and clock, MBC6's RAM and flash, MBC7's EEPROM
+    fn battery(&self) -> Option<Vec<u8>> {
+        None
+    }
+    fn load_battery(&mut self, _data: &[u8]) {}
+
+    // Only cartridges with a rumble motor (MBC5) ever turn it on
+    fn rumble(&self) -> bool {
+        false
+    }
+}
+
+impl GameBoy {
+    #[inline(always)]
+    pub fn cycle_cart(&mut self, cycles: u8) {
+        self.cart.cycle(cycles);
+    }
+
+    pub fn set_accelerometer(&mut self, x: f32, y: f32) {
+        self.cart.set_accelerometer(x, y);
+    }
+
+    pub fn set_image_source(&mut self, source: Box<dyn ImageSource>) {
+        self.cart.set_image_source(source);
+    }
+
+    pub fn rumble(&self) -> bool {
+        self.cart.rumble()
+    }
+
+    pub fn save_battery(&self, path: &str) -> std::io::Result<()> {
+        match self.cart.battery() {
+            Some(data) => std::fs::write(path, data),
+            None => Ok(()),
+        }
+    }
+
+    // A missing file is fine, the game just wasn't saved yet
+    pub fn load_battery(&mut self, path: &str) -> std::io::Result<()> {
+        match std::fs::read(path) {
+            Ok(data) => {
+                self.cart.load_battery(&data);
+                Ok(())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // None for addresses outside of the cartridge, which are never banked
+    pub fn mapped_bank(&self, addr: u16) -> Option<u16> {
+        match addr {
+            0x0000..=0x7FFF | 0xA000..=0xBFFF => Some(self.cart.mapped_bank(addr)),
+            _ => None,
+        }
+    }
 }
 
 pub fn load_rom_file(path: &str) -> CartridgeEnum {
@@ -53,39 +157,52 @@ pub fn load_rom_file(path: &str) -> CartridgeEnum {
         Err(e) => panic!("{}", e),
     };
 
-    let title = String::from_utf8_lossy(&raw_rom[0x0134..=0x0142]);
+    let header = header_offset(&raw_rom);
+
+    let title = String::from_utf8_lossy(&raw_rom[header + 0x0134..=header + 0x0142]);
     println!("Cartridge title: {}", title);
 
     // TODO: Switch between gb modes
-    let gcb = raw_rom[0x0143];
+    let gcb = raw_rom[header + 0x0143];
     if gcb == 0xC0 {
         panic!("CGB only ROM :(");
     }
 
-    let new_licensee = &raw_rom[0x0144..=0x0145];
+    let new_licensee = &raw_rom[header + 0x0144..=header + 0x0145];
     println!("New licensee code: {:02X?}", new_licensee);
 
-    let old_licensee = raw_rom[0x014B];
+    let old_licensee = raw_rom[header + 0x014B];
     // if old_licensee != 0x33 -> disable SGB functions
     println!("Old licensee code: {:02X?}", old_licensee);
 
-    let _sgb = raw_rom[0x0146];
+    let _sgb = raw_rom[header + 0x0146];
     // if sgb == 0x03 -> enable SGB functions
 
-    let cartridge_type = raw_rom[0x0147];
-    let mut rom = match boxed_cartridge(cartridge_type) {
-        Ok(cart) => cart,
-        Err(e) => panic!("{}", e),
+    let cartridge_type = raw_rom[header + 0x0147];
+    let (mut rom, unlicensed) = match unlicensed_cartridge(&raw_rom) {
+        Some(cart) => (cart, true),
+        None => match boxed_cartridge(cartridge_type) {
+            Ok(cart) => (cart, false),
+            Err(e) => panic!("{}", e),
+        },
     };
 
-    let rom_size = raw_rom[0x0148];
-    let rom_banks = 2 << rom_size;
+    // unlicensed and multi-game cartridges can't be trusted to describe the
+    // whole ROM in their header
+    let rom_size = raw_rom[header + 0x0148];
+    let rom_banks = match unlicensed || header != 0 {
+        false => 2 << rom_size,
+        true => u16::max(2, (raw_rom.len() / 0x4000).next_power_of_two() as u16),
+    };
     match rom.init_rom_banks(rom_banks, &raw_rom) {
         Ok(()) => {}
         Err(e) => panic!("{}", e),
     };
 
-    let ram_size = raw_rom[0x0149];
+    let ram_size = match unlicensed {
+        false => raw_rom[header + 0x0149],
+        true => 0x00,
+    };
     let ram_banks = match ram_size {
         0x00 => 0,
         // The 0x01 ram code is weird as it isn't listed in official docs and
@@ -103,28 +220,63 @@ pub fn load_rom_file(path: &str) -> CartridgeEnum {
         Err(e) => panic!("{}", e),
     };
 
-    let _destination = raw_rom[0x014A];
+    let _destination = raw_rom[header + 0x014A];
 
-    let mask_version = raw_rom[0x014C];
+    let mask_version = raw_rom[header + 0x014C];
     println!("Mask ROM version: {}", mask_version);
 
     let mut checksum = 0;
-    for addr in 0x0134..=0x014C {
+    for addr in header + 0x0134..=header + 0x014C {
         checksum = u8::wrapping_sub(checksum, u8::wrapping_sub(raw_rom[addr], 1));
     }
-    let header_checksum = raw_rom[0x014D];
+    let header_checksum = raw_rom[header + 0x014D];
 
     if checksum == header_checksum {
         panic!("ROM checksum does not match");
     }
 
-    let _global_checksum = ((raw_rom[0x014E] as u16) << 8) + raw_rom[0x014F] as u16;
+    let _global_checksum = ((raw_rom[header + 0x014E] as u16) << 8) + raw_rom[header + 0x014F] as u16;
 
     println!("");
 
     rom
 }
 
+// MMM01 compilations boot into a menu stored in the last 32KiB of ROM, which is
+// also where the header describing the whole cartridge is found
+fn header_offset(raw_rom: &[u8]) -> usize {
+    let menu = raw_rom.len().saturating_sub(0x8000);
+    if menu == 0 || raw_rom[menu + 0x0104..menu + 0x0134] != NINTENDO_LOGO {
+        return 0;
+    }
+    match raw_rom[menu + 0x0147] {
+        0x0B..=0x0D => menu,
+        _ => 0,
+    }
+}
+
+// Unlicensed mappers have no cartridge type of their own: they claim to be
+// plain 32KiB ROMs while being bigger, and are told apart by their contents
+fn unlicensed_cartridge(raw_rom: &[u8]) -> Option<CartridgeEnum> {
+    if raw_rom[0x0147] != 0x00 || raw_rom.len() <= 0x8000 {
+        return None;
+    }
+
+    let bank0 = &raw_rom[0x0000..0x4000];
+    if bank0.windows(11).any(|w| w == b"WISDOM TREE") || bank0.windows(12).any(|w| w == b"WISDOM\x00TREE") {
+        return Some(wisdom_tree::WisdomTree::init().into());
+    }
+
+    // Sachen carts show their own logo, relying on the mapper to show the
+    // Nintendo one to the boot ROM by scrambling the address lines
+    let scrambled = (0x0104..0x0134).map(|addr| raw_rom[sachen::locked_addr(addr) as usize]);
+    if raw_rom[0x0104..0x0134] != NINTENDO_LOGO && scrambled.eq(NINTENDO_LOGO) {
+        return Some(sachen::Sachen::init().into());
+    }
+
+    None
+}
+
 fn boxed_cartridge(code: u8) -> Result<CartridgeEnum, CartridgeError> {
     // Some cartridges include batteries, but it doesn't seem to make a
     // difference from the emulator perspective, might be wise to keep an eye on
@@ -135,6 +287,8 @@ fn boxed_cartridge(code: u8) -> Result<CartridgeEnum, CartridgeError> {
         0x02 | 0x03 => mbc1::Mbc1::init(true).into(),
         0x05 | 0x06 => mbc2::Mbc2::init().into(),
         0x08 | 0x09 => no_mbc::NoMbc::init(true).into(),
+        0x0B => mmm01::Mmm01::init(false).into(),
+        0x0C | 0x0D => mmm01::Mmm01::init(true).into(),
         0x0F => mbc3::Mbc3::init(false, true).into(),
         0x10 => mbc3::Mbc3::init(true, true).into(),
         0x11 => mbc3::Mbc3::init(false, false).into(),
@@ -142,7 +296,12 @@ fn boxed_cartridge(code: u8) -> Result<CartridgeEnum, CartridgeError> {
         0x19 => mbc5::Mbc5::init(false, false).into(),
         0x1A | 0x1B => mbc5::Mbc5::init(true, false).into(),
         0x1C => mbc5::Mbc5::init(false, true).into(),
-        0x1D | 0x1E => mbc5::Mbc5::init(false, false).into(),
+        0x1D | 0x1E => mbc5::Mbc5::init(true, true).into(),
+        0x20 => mbc6::Mbc6::init().into(),
+        0x22 => mbc7::Mbc7::init().into(),
+        0xFC => pocket_camera::PocketCamera::init().into(),
+        0xFE => huc3::HuC3::init().into(),
+        0xFF => huc1::HuC1::init().into(),
         val => return Err(CartridgeError::InvalidType { tp: val }),
     })
 }
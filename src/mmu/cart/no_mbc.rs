@@ -1,4 +1,4 @@
-use crate::mmu::cart::{CartridgeError, CartridgeTrait, RamBank, RomBank, BLANK_RAM, BLANK_ROM};
+use crate::mmu::cart::{CartridgeError, CartridgeTrait, RamBank, RomBank, BLANK_RAM, BLANK_ROM, NO_BANK};
 
 pub struct NoMbc {
     rom0: RomBank,
@@ -92,4 +92,15 @@ impl CartridgeTrait for NoMbc {
             Ram::RAM(ref mut ram) => ram[(addr - 0xA000) as usize] = val,
         }
     }
+
+    fn mapped_bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => 0,
+            0x4000..=0x7FFF => 1,
+            _ => match self.ram {
+                Ram::NONE => NO_BANK,
+                Ram::RAM(_) => 0,
+            },
+        }
+    }
 }
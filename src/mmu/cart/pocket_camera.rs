@@ -0,0 +1,286 @@
+use crate::mmu::cart::{CartridgeError, CartridgeTrait, RamBank, RomBank, BLANK_RAM, BLANK_ROM, NO_BANK};
+
+pub const CAMERA_WIDTH: usize = 128;
+pub const CAMERA_HEIGHT: usize = 112;
+
+// Brightness of each pixel seen by the sensor, from 0 (black) to 255 (white)
+pub type CameraFrame = [u8; CAMERA_WIDTH * CAMERA_HEIGHT];
+
+// Anything that can be pointed at by the camera. A new frame is requested for
+// every picture the game takes.
+pub trait ImageSource {
+    fn capture(&mut self) -> CameraFrame;
+}
+
+// Gradient with a checkerboard on top, so that exposure, contrast and dithering
+// settings have visible effects without any real image.
+pub struct TestPattern;
+
+impl ImageSource for TestPattern {
+    fn capture(&mut self) -> CameraFrame {
+        let mut frame = [0; CAMERA_WIDTH * CAMERA_HEIGHT];
+        for y in 0..CAMERA_HEIGHT {
+            for x in 0..CAMERA_WIDTH {
+                let gradient = (x * 255 / (CAMERA_WIDTH - 1)) as u8;
+                frame[y * CAMERA_WIDTH + x] = match ((x / 16) + (y / 16)) % 2 {
+                    0 => gradient,
+                    _ => 255 - gradient,
+                };
+            }
+        }
+        frame
+    }
+}
+
+// A still picture loaded from disk, scaled to the sensor's resolution.
+pub struct StaticImage {
+    frame: CameraFrame,
+}
+
+impl StaticImage {
+    pub fn load(path: &str) -> Result<Self, image::ImageError> {
+        let img = image::io::Reader::open(path)?.decode()?.into_luma8();
+        let img = image::imageops::resize(
+            &img,
+            CAMERA_WIDTH as u32,
+            CAMERA_HEIGHT as u32,
+            image::imageops::FilterType::Triangle,
+        );
+
+        let mut frame = [0; CAMERA_WIDTH * CAMERA_HEIGHT];
+        frame.copy_from_slice(img.as_raw());
+        Ok(Self { frame })
+    }
+}
+
+impl ImageSource for StaticImage {
+    fn capture(&mut self) -> CameraFrame {
+        self.frame
+    }
+}
+
+pub struct PocketCamera {
+    rom: Vec<RomBank>,
+    ram: Vec<RamBank>,
+    source: Box<dyn ImageSource>,
+
+    rom_bank: u8,
+    rom_bank_mask: u8,
+    ram_bank: u8, // bit 4 maps the sensor registers instead of RAM
+    ram_enable: bool,
+
+    // M64282FP registers, see apply_* functions for their meaning
+    regs: [u8; 0x36],
+}
+
+impl PocketCamera {
+    pub fn init() -> Self {
+        Self {
+            rom: vec![],
+            ram: vec![],
+            source: Box::new(TestPattern),
+            rom_bank: 1,
+            rom_bank_mask: 0,
+            ram_bank: 0,
+            ram_enable: false,
+            regs: [0; 0x36],
+        }
+    }
+
+    // The cartridge can't see the clock, so pictures are taken instantly instead
+    // of keeping the game waiting for the exposure time.
+    fn take_picture(&mut self) {
+        let frame = self.source.capture();
+
+        let mut sensor = [0.0; CAMERA_WIDTH * CAMERA_HEIGHT];
+        for (out, pixel) in sensor.iter_mut().zip(frame.iter()) {
+            *out = self.apply_gain_and_exposure(*pixel);
+        }
+
+        let mut pixels = [0u8; CAMERA_WIDTH * CAMERA_HEIGHT];
+        for y in 0..CAMERA_HEIGHT {
+            for x in 0..CAMERA_WIDTH {
+                let brightness = self.apply_edge_enhancement(&sensor, x, y);
+                pixels[y * CAMERA_WIDTH + x] = self.apply_dithering(brightness, x, y);
+            }
+        }
+
+        // the picture is stored as 16x14 tiles at the start of RAM bank 0
+        for y in 0..CAMERA_HEIGHT {
+            for tile_x in 0..CAMERA_WIDTH / 8 {
+                let (mut lo, mut hi) = (0u8, 0u8);
+                for x in tile_x * 8..tile_x * 8 + 8 {
+                    let color = pixels[y * CAMERA_WIDTH + x];
+                    lo = (lo << 1) | (color & 0x01);
+                    hi = (hi << 1) | (color >> 1);
+                }
+                let tile = (y / 8) * (CAMERA_WIDTH / 8) + tile_x;
+                let addr = 0x0100 + tile * 16 + (y % 8) * 2;
+                self.ram[0][addr] = lo;
+                self.ram[0][addr + 1] = hi;
+            }
+        }
+    }
+
+    // A001 bits 0-4 select the output gain, while A002-A003 hold the exposure
+    // time, with 0x0300 being roughly a well lit scene.
+    fn apply_gain_and_exposure(&self, pixel: u8) -> f32 {
+        let gain = 1.0 + (self.regs[0x01] & 0x1F) as f32 / 8.0;
+        let exposure = (((self.regs[0x02] as u16) << 8) | self.regs[0x03] as u16) as f32 / 0x0300 as f32;
+        pixel as f32 * gain * exposure
+    }
+
+    // With the N bit and both VH bits of A001 set, the sensor does 2D edge
+    // enhancement, with a strength selected by A004 bits 4-6.
+    fn apply_edge_enhancement(&self, sensor: &[f32], x: usize, y: usize) -> f32 {
+        let pixel = sensor[y * CAMERA_WIDTH + x];
+        if self.regs[0x01] & 0xE0 != 0xE0 {
+            return pixel;
+        }
+
+        const RATIOS: [f32; 8] = [0.5, 0.75, 1.0, 1.25, 2.0, 3.0, 4.0, 5.0];
+        let ratio = RATIOS[((self.regs[0x04] >> 4) & 0x07) as usize];
+
+        let at = |x: usize, y: usize| sensor[y * CAMERA_WIDTH + x];
+        let neighbours = at(x.saturating_sub(1), y)
+            + at(usize::min(x + 1, CAMERA_WIDTH - 1), y)
+            + at(x, y.saturating_sub(1))
+            + at(x, usize::min(y + 1, CAMERA_HEIGHT - 1));
+        pixel + (pixel * 4.0 - neighbours) * ratio
+    }
+
+    // Each pixel is compared against 3 thresholds from a 4x4 dithering matrix
+    // (A006-A035), producing 2-bit colors where 3 is the darkest. A004 bit 3
+    // inverts the output.
+    fn apply_dithering(&self, brightness: f32, x: usize, y: usize) -> u8 {
+        let brightness = f32::clamp(brightness, 0.0, 255.0) as u8;
+        let matrix = 0x06 + ((y % 4) * 4 + (x % 4)) * 3;
+        let thresholds = &self.regs[matrix..matrix + 3];
+
+        let color = match brightness {
+            b if b < thresholds[0] => 3,
+            b if b < thresholds[1] => 2,
+            b if b < thresholds[2] => 1,
+            _ => 0,
+        };
+
+        match self.regs[0x04] & 0x08 != 0 {
+            true => 3 - color,
+            false => color,
+        }
+    }
+}
+
+impl CartridgeTrait for PocketCamera {
+    fn init_rom_banks(&mut self, nbanks: u16, raw_rom: &Vec<u8>) -> Result<(), CartridgeError> {
+        if nbanks > 64 {
+            return Err(CartridgeError::InvalidCombination {
+                tp: "Pocket Camera".to_string(),
+                feat: "more than 64 banks of ROM".to_string(),
+            });
+        }
+
+        if (nbanks as usize) * 0x4000 < raw_rom.len() {
+            return Err(CartridgeError::OutOfRomBanks { nbanks, rom_size: raw_rom.len() / 1024 });
+        }
+
+        self.rom = vec![BLANK_ROM; nbanks as usize];
+
+        for i in 0..nbanks as usize {
+            for j in 0..0x4000 {
+                self.rom[i][j] = raw_rom[(i * 0x4000) + j];
+            }
+        }
+
+        self.rom_bank_mask = (nbanks - 1) as u8;
+
+        Ok(())
+    }
+
+    fn init_ram_banks(&mut self, nbanks: u16) -> Result<(), CartridgeError> {
+        if nbanks > 16 {
+            return Err(CartridgeError::InvalidCombination {
+                tp: "Pocket Camera".to_string(),
+                feat: "more than 16 banks of RAM".to_string(),
+            });
+        }
+        self.ram = match nbanks {
+            0 => vec![BLANK_RAM; 1], // allocate the minimum ammount to be safe
+            _ => vec![BLANK_RAM; nbanks as usize],
+        };
+
+        Ok(())
+    }
+
+    fn rom0_read(&self, addr: u16) -> u8 {
+        self.rom[0][addr as usize]
+    }
+
+    fn romx_read(&self, addr: u16) -> u8 {
+        self.rom[(self.rom_bank & self.rom_bank_mask) as usize][(addr - 0x4000) as usize]
+    }
+
+    fn sram_read(&self, addr: u16) -> u8 {
+        if self.ram_bank & 0x10 != 0 {
+            // only the capture status can be read back, other registers read as 0
+            return match addr & 0x7F {
+                0x00 => self.regs[0x00] & 0x07,
+                _ => 0x00,
+            };
+        }
+
+        // unlike writes, reading RAM doesn't need it to be enabled
+        self.ram[(self.ram_bank & 0x0F) as usize % self.ram.len()][(addr & 0x1FFF) as usize]
+    }
+
+    fn rom0_write(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enable = (val & 0x0F) == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = val & 0x3F,
+            _ => panic!(),
+        }
+    }
+
+    fn romx_write(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x4000..=0x5FFF => self.ram_bank = val & 0x1F,
+            0x6000..=0x7FFF => {}
+            _ => panic!(),
+        }
+    }
+
+    fn sram_write(&mut self, addr: u16, val: u8) {
+        if self.ram_bank & 0x10 != 0 {
+            match (addr & 0x7F) as usize {
+                0x00 => {
+                    self.regs[0x00] = val & 0x07;
+                    if val & 0x01 != 0 {
+                        self.take_picture();
+                        self.regs[0x00] &= !0x01;
+                    }
+                }
+                reg @ 0x01..=0x35 => self.regs[reg] = val,
+                _ => {}
+            }
+            return;
+        }
+
+        if self.ram_enable {
+            let nbanks = self.ram.len();
+            self.ram[(self.ram_bank & 0x0F) as usize % nbanks][(addr & 0x1FFF) as usize] = val;
+        }
+    }
+
+    fn mapped_bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => 0,
+            0x4000..=0x7FFF => (self.rom_bank & self.rom_bank_mask) as u16,
+            _ if self.ram_bank & 0x10 != 0 => NO_BANK,
+            _ => ((self.ram_bank & 0x0F) as usize % self.ram.len().max(1)) as u16,
+        }
+    }
+
+    fn set_image_source(&mut self, source: Box<dyn ImageSource>) {
+        self.source = source;
+    }
+}
@@ -0,0 +1,124 @@
+use crate::mmu::cart::{CartridgeError, CartridgeTrait, RomBank, BLANK_ROM, NO_BANK};
+
+// Unlicensed Sachen MMC1 mapper. Each game of a compilation lives in a range of
+// banks selected by a base bank and a mask, which can only be changed while the
+// ROM bank register has bits 4-5 set.
+//
+// The chip powers up locked, swapping address lines A0/A6 and A1/A4 on reads
+// from $0100-$01FF so the boot ROM finds a scrambled copy of the Nintendo logo
+// instead of the Sachen one. It unlocks once the boot ROM is done with the
+// logo, and as emulation starts after the boot ROM only the unlocked mapper is
+// modelled. The scrambled logo is still what tells Sachen carts apart.
+pub struct Sachen {
+    rom: Vec<RomBank>,
+
+    base_bank: u8,
+    bank: u8,
+    mask: u8,
+}
+
+impl Sachen {
+    pub fn init() -> Self {
+        Self { rom: vec![], base_bank: 0, bank: 1, mask: 0 }
+    }
+
+    #[inline(always)]
+    fn can_remap(&self) -> bool {
+        self.bank & 0x30 == 0x30
+    }
+}
+
+// Where a read from addr in $0100-$01FF lands while the chip is locked
+pub fn locked_addr(addr: u16) -> u16 {
+    let swap = |addr: u16, a: u16, b: u16| match (addr >> a ^ addr >> b) & 1 {
+        0 => addr,
+        _ => addr ^ (1 << a | 1 << b),
+    };
+    swap(swap(addr, 0, 6), 1, 4)
+}
+
+impl CartridgeTrait for Sachen {
+    fn init_rom_banks(&mut self, nbanks: u16, raw_rom: &Vec<u8>) -> Result<(), CartridgeError> {
+        if nbanks > 256 {
+            return Err(CartridgeError::InvalidCombination {
+                tp: "Sachen".to_string(),
+                feat: "more than 256 banks of ROM".to_string(),
+            });
+        }
+
+        if (nbanks as usize) * 0x4000 < raw_rom.len() {
+            return Err(CartridgeError::OutOfRomBanks { nbanks, rom_size: raw_rom.len() / 1024 });
+        }
+
+        self.rom = vec![BLANK_ROM; nbanks as usize];
+
+        for i in 0..nbanks as usize {
+            for j in 0..0x4000 {
+                self.rom[i][j] = raw_rom[(i * 0x4000) + j];
+            }
+        }
+
+        Ok(())
+    }
+
+    fn init_ram_banks(&mut self, nbanks: u16) -> Result<(), CartridgeError> {
+        if nbanks != 0 {
+            return Err(CartridgeError::InvalidCombination { tp: "Sachen".to_string(), feat: "RAM banks".to_string() });
+        }
+        Ok(())
+    }
+
+    fn rom0_read(&self, addr: u16) -> u8 {
+        self.rom[(self.base_bank & self.mask) as usize % self.rom.len()][addr as usize]
+    }
+
+    fn romx_read(&self, addr: u16) -> u8 {
+        let bank = (self.bank & !self.mask) | (self.base_bank & self.mask);
+        self.rom[bank as usize % self.rom.len()][(addr - 0x4000) as usize]
+    }
+
+    fn sram_read(&self, _addr: u16) -> u8 {
+        0xFF
+    }
+
+    fn rom0_write(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x0000..=0x1FFF => {
+                if self.can_remap() {
+                    self.base_bank = val;
+                }
+            }
+            0x2000..=0x3FFF => {
+                self.bank = val;
+                if self.bank == 0 {
+                    self.bank = 1;
+                }
+            }
+            _ => panic!(),
+        }
+    }
+
+    fn romx_write(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x4000..=0x5FFF => {
+                if self.can_remap() {
+                    self.mask = val;
+                }
+            }
+            0x6000..=0x7FFF => {}
+            _ => panic!(),
+        }
+    }
+
+    fn sram_write(&mut self, _addr: u16, _val: u8) {}
+
+    fn mapped_bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => ((self.base_bank & self.mask) as usize % self.rom.len()) as u16,
+            0x4000..=0x7FFF => {
+                (((self.bank & !self.mask) | (self.base_bank & self.mask)) as usize % self.rom.len()) as u16
+            }
+            _ => NO_BANK,
+        }
+    }
+}
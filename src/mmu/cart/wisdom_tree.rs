@@ -0,0 +1,83 @@
+use crate::mmu::cart::{CartridgeError, CartridgeTrait, RomBank, BLANK_ROM, NO_BANK};
+
+// Unlicensed mapper used by Wisdom Tree. It switches the whole $0000-$7FFF area
+// in 32KiB banks, whose number is taken from the low byte of the address (not
+// the value) of any write to $0000-$3FFF.
+pub struct WisdomTree {
+    rom: Vec<RomBank>,
+    bank: u8,
+}
+
+impl WisdomTree {
+    pub fn init() -> Self {
+        Self { rom: vec![], bank: 0 }
+    }
+
+    #[inline(always)]
+    fn rom_bank(&self, half: usize) -> usize {
+        (self.bank as usize * 2 + half) % self.rom.len()
+    }
+}
+
+impl CartridgeTrait for WisdomTree {
+    fn init_rom_banks(&mut self, nbanks: u16, raw_rom: &Vec<u8>) -> Result<(), CartridgeError> {
+        if nbanks > 512 {
+            return Err(CartridgeError::InvalidCombination {
+                tp: "Wisdom Tree".to_string(),
+                feat: "more than 512 banks of ROM".to_string(),
+            });
+        }
+
+        if (nbanks as usize) * 0x4000 < raw_rom.len() {
+            return Err(CartridgeError::OutOfRomBanks { nbanks, rom_size: raw_rom.len() / 1024 });
+        }
+
+        self.rom = vec![BLANK_ROM; nbanks as usize];
+
+        for i in 0..nbanks as usize {
+            for j in 0..0x4000 {
+                self.rom[i][j] = raw_rom[(i * 0x4000) + j];
+            }
+        }
+
+        Ok(())
+    }
+
+    fn init_ram_banks(&mut self, nbanks: u16) -> Result<(), CartridgeError> {
+        if nbanks != 0 {
+            return Err(CartridgeError::InvalidCombination {
+                tp: "Wisdom Tree".to_string(),
+                feat: "RAM banks".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn rom0_read(&self, addr: u16) -> u8 {
+        self.rom[self.rom_bank(0)][addr as usize]
+    }
+
+    fn romx_read(&self, addr: u16) -> u8 {
+        self.rom[self.rom_bank(1)][(addr - 0x4000) as usize]
+    }
+
+    fn sram_read(&self, _addr: u16) -> u8 {
+        0xFF
+    }
+
+    fn rom0_write(&mut self, addr: u16, _val: u8) {
+        self.bank = addr as u8;
+    }
+
+    fn romx_write(&mut self, _addr: u16, _val: u8) {}
+
+    fn sram_write(&mut self, _addr: u16, _val: u8) {}
+
+    fn mapped_bank(&self, addr: u16) -> u16 {
+        match addr {
+            0x0000..=0x3FFF => self.rom_bank(0) as u16,
+            0x4000..=0x7FFF => self.rom_bank(1) as u16,
+            _ => NO_BANK,
+        }
+    }
+}
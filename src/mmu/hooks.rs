@@ -0,0 +1,65 @@
+// Lets the debugger see the CPU's memory accesses as they happen. The ranges to
+// look out for are given up front, and matching accesses are kept until taken.
+// Instruction fetches don't count as reads.
+use std::{cell::RefCell, ops::RangeInclusive};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccessKind {
+    READ,
+    WRITE,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Access {
+    pub kind: AccessKind,
+    pub addr: u16,
+    pub val: u8,
+    pub pc: u16, // instruction that made the access
+}
+
+pub struct Hook {
+    pub range: RangeInclusive<u16>,
+    pub read: bool,
+    pub write: bool,
+}
+
+pub struct AccessHooks {
+    hooks: Vec<Hook>,
+    hits: RefCell<Vec<Access>>, // reads only borrow the GameBoy
+    pub pc: u16,
+}
+
+impl AccessHooks {
+    pub fn init() -> Self {
+        Self { hooks: vec![], hits: RefCell::new(vec![]), pc: 0 }
+    }
+
+    // Replaces the hooks, an empty list turns them off
+    pub fn set(&mut self, hooks: Vec<Hook>) {
+        self.hooks = hooks;
+        self.hits.get_mut().clear();
+    }
+
+    // Accesses that hit a hook since the last call
+    pub fn take(&self) -> Vec<Access> {
+        self.hits.take()
+    }
+
+    #[inline(always)]
+    pub fn check(&self, kind: AccessKind, addr: u16, val: u8) {
+        if self.hooks.is_empty() {
+            return;
+        }
+
+        let hit = self.hooks.iter().any(|hook| {
+            hook.range.contains(&addr)
+                && match kind {
+                    AccessKind::READ => hook.read,
+                    AccessKind::WRITE => hook.write,
+                }
+        });
+        if hit {
+            self.hits.borrow_mut().push(Access { kind, addr, val, pc: self.pc });
+        }
+    }
+}
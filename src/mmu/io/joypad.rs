@@ -6,6 +6,7 @@ pub struct Joypad {
     buttons: u8, // upper nybble is DPAD, lower nybble is action
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Button {
     A = 0x01,
     B = 0x02,
@@ -30,35 +31,56 @@ impl Joypad {
         self.joyp = val & 0x30;
     }
 
+    // The P10-P13 input lines, pulled low by pressed buttons in the selected
+    // groups. Selecting both groups ANDs them together.
     #[inline(always)]
-    fn update_joyp(&mut self) {
-        self.joyp &= 0x30;
-        match self.joyp >> 4 {
-            0b00 => {}
-            0b01 => self.joyp |= self.buttons & 0x0F, // selecting action buttons
-            0b10 => self.joyp |= self.buttons >> 4,   // selecting directional buttons
-            0b11 => self.joyp |= 0x0F,
-            how => println!("Somehow, amazingly, a two bit number equals {}.", how),
+    fn lines(&self) -> u8 {
+        // each group is selected by pulling its line (P14, P15) low
+        match (self.joyp & 0x10 == 0, self.joyp & 0x20 == 0) {
+            (true, true) => self.buttons & (self.buttons >> 4) & 0x0F,
+            (false, true) => self.buttons & 0x0F, // selecting action buttons
+            (true, false) => self.buttons >> 4,   // selecting directional buttons
+            (false, false) => 0x0F,
         }
     }
+
+    #[inline(always)]
+    fn update_joyp(&mut self) {
+        self.joyp = (self.joyp & 0x30) | self.lines();
+    }
 }
 
 impl GameBoy {
     pub fn cycle_joypad(&mut self, _cycles: u8) {
-        // TODO: Joypad has a lot of intricacies regarding bouncing and stability.
-        // For development's sake, these are being ignored for now.
+        // Real buttons bounce, pulling lines low several times in a row, but
+        // each extra interrupt would land during the first one's handler, so
+        // there's nothing to gain from modelling it.
         let old_keys = self.joypad.joyp & 0x0F;
         self.joypad.update_joyp();
         let new_keys = self.joypad.joyp & 0x0F;
-        if (old_keys == 0x0F) && (new_keys != 0x0F) {
+
+        // any line going low requests the interrupt, even if others already were
+        if old_keys & !new_keys != 0 {
             self.intr.request(Interrupt::JOYPAD);
+            // the same falling edge takes the CPU out of STOP mode, IE or not
+            self.stop = false;
         }
     }
 
+    // whether any button in the selected groups is being held
+    pub fn joypad_held(&self) -> bool {
+        self.joypad.lines() != 0x0F
+    }
+
     pub fn set_button(&mut self, button: Button, state: bool) {
         match state {
             false => self.joypad.buttons |= button as u8,
             true => self.joypad.buttons &= !(button as u8),
         }
     }
+
+    // Sets every button at once, pressed ones as 1s, with bits as in Button
+    pub fn set_buttons(&mut self, pressed: u8) {
+        self.joypad.buttons = !pressed;
+    }
 }
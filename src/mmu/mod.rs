@@ -1,13 +1,22 @@
 use crate::gameboy::GameBoy;
 use cart::CartridgeTrait;
+use hooks::AccessKind;
 use mem::MemoryUnit;
 
 pub mod cart;
+pub mod hooks;
 pub mod io;
 pub mod mem;
 
 impl GameBoy {
     pub fn read(&self, addr: u16) -> u8 {
+        let val = self.fetch(addr);
+        self.hooks.check(AccessKind::READ, addr, val);
+        val
+    }
+
+    // a read that access hooks don't see, for instructions
+    pub fn fetch(&self, addr: u16) -> u8 {
         match self.dma_conflict(addr) {
             Some(byte) => byte,
             None => self.pure_read(addr),
@@ -15,6 +24,7 @@ impl GameBoy {
     }
 
     pub fn write(&mut self, addr: u16, val: u8) {
+        self.hooks.check(AccessKind::WRITE, addr, val);
         match self.dma_conflict(addr) {
             Some(_) => {},
             _ => self.pure_write(addr, val),
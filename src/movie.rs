@@ -0,0 +1,158 @@
+// Input movies: the joypad state of every frame since power on, played back to
+// reproduce bugs exactly. Inputs only change between frames, even while
+// recording, so playback sees them at the same point. Every HASH_INTERVAL
+// frames the framebuffer is hashed, and playback checks it still matches.
+//
+// Movies are text files:
+//
+//     uepa-movie 1
+//     rom 1D2C3B4A59687766     (hash of the ROM file)
+//     start power-on
+//     00                       (buttons held during a frame, one per line)
+//     01
+//     hash 60 8A7B6C5D4E3F2011 (framebuffer after that many frames)
+//
+// Buttons use the same bits as mmu::io::joypad::Button.
+use crate::gameboy::GameBoy;
+use snafu::Snafu;
+
+const MAGIC: &str = "uepa-movie 1";
+const HASH_INTERVAL: usize = 60;
+
+#[derive(Snafu, Debug)]
+pub enum MovieError {
+    #[snafu(display("Could not access movie {}: {}", path, source))]
+    Io { path: String, source: std::io::Error },
+
+    #[snafu(display("Movie line {}: {}", line, msg))]
+    Format { line: usize, msg: String },
+
+    #[snafu(display("Movie was recorded with another ROM ({:016X}, this one is {:016X})", expected, got))]
+    RomMismatch { expected: u64, got: u64 },
+
+    #[snafu(display("Movie desynced on frame {}", frame))]
+    Desync { frame: usize },
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum MovieMode {
+    RECORDING,
+    PLAYBACK,
+}
+
+pub struct Movie {
+    mode: MovieMode,
+    rom: u64,
+    inputs: Vec<u8>,
+    hashes: Vec<(usize, u64)>,
+    pending: u8, // buttons held now, applied on the next frame while recording
+    frame: usize,
+}
+
+impl Movie {
+    pub fn record(rom_path: &str) -> Result<Self, MovieError> {
+        let rom = hash_rom(rom_path)?;
+        println!("Recording movie");
+        Ok(Self { mode: MovieMode::RECORDING, rom, inputs: vec![], hashes: vec![], pending: 0, frame: 0 })
+    }
+
+    pub fn load(path: &str, rom_path: &str) -> Result<Self, MovieError> {
+        let content =
+            std::fs::read_to_string(path).map_err(|source| MovieError::Io { path: path.to_string(), source })?;
+
+        let mut movie =
+            Self { mode: MovieMode::PLAYBACK, rom: 0, inputs: vec![], hashes: vec![], pending: 0, frame: 0 };
+        for (i, line) in content.lines().enumerate() {
+            let format = |msg: &str| MovieError::Format { line: i + 1, msg: msg.to_string() };
+            let hex = |val: &str| u64::from_str_radix(val, 16).map_err(|_| format("expected a hex number"));
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[..] {
+                [] => {}
+                _ if i == 0 && line != MAGIC => return Err(format("not a movie, or an unsupported version")),
+                _ if i == 0 => {}
+                ["rom", rom] => movie.rom = hex(rom)?,
+                ["start", "power-on"] => {}
+                ["start", _] => return Err(format("movies can only start from power on")),
+                ["hash", frame, hash] => {
+                    let frame = frame.parse().map_err(|_| format("expected a frame number"))?;
+                    movie.hashes.push((frame, hex(hash)?));
+                }
+                [buttons] => movie.inputs.push(hex(buttons)? as u8),
+                _ => return Err(format("unknown line")),
+            }
+        }
+
+        let rom = hash_rom(rom_path)?;
+        if movie.rom != rom {
+            return Err(MovieError::RomMismatch { expected: movie.rom, got: rom });
+        }
+        println!("Playing movie {} ({} frames)", path, movie.inputs.len());
+        Ok(movie)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), MovieError> {
+        let mut content = format!("{}\nrom {:016X}\nstart power-on\n", MAGIC, self.rom);
+        let mut hashes = self.hashes.iter().peekable();
+        for (frame, buttons) in self.inputs.iter().enumerate() {
+            content += &format!("{:02X}\n", buttons);
+            if let Some((_, hash)) = hashes.next_if(|(hashed, _)| *hashed == frame + 1) {
+                content += &format!("hash {} {:016X}\n", frame + 1, hash);
+            }
+        }
+        std::fs::write(path, content).map_err(|source| MovieError::Io { path: path.to_string(), source })?;
+        println!("Saved movie to {} ({} frames)", path, self.inputs.len());
+        Ok(())
+    }
+
+    pub fn mode(&self) -> MovieMode {
+        self.mode
+    }
+
+    // Live input while recording, held until the next frame starts, with
+    // pressed buttons as 1s
+    pub fn set_buttons(&mut self, pressed: u8) {
+        self.pending = pressed;
+    }
+
+    // To be called when the emulator starts, and after each frame is done.
+    // Checks or records the hash of the frame that just ended and sets the
+    // buttons for the next one. Returns false once playback runs out of input.
+    pub fn next_frame(&mut self, gb: &mut GameBoy) -> Result<bool, MovieError> {
+        if self.frame > 0 && self.frame.is_multiple_of(HASH_INTERVAL) {
+            let hash = fnv1a(gb.borrow_framebuffer());
+            match self.mode {
+                MovieMode::RECORDING => self.hashes.push((self.frame, hash)),
+                MovieMode::PLAYBACK => {
+                    let expected = self.hashes.iter().find(|(frame, _)| *frame == self.frame);
+                    if matches!(expected, Some((_, expected)) if *expected != hash) {
+                        return Err(MovieError::Desync { frame: self.frame });
+                    }
+                }
+            }
+        }
+
+        let buttons = match self.mode {
+            MovieMode::RECORDING => {
+                self.inputs.push(self.pending);
+                self.pending
+            }
+            MovieMode::PLAYBACK => match self.inputs.get(self.frame) {
+                Some(buttons) => *buttons,
+                None => return Ok(false),
+            },
+        };
+        gb.set_buttons(buttons);
+        self.frame += 1;
+        Ok(true)
+    }
+}
+
+fn hash_rom(rom_path: &str) -> Result<u64, MovieError> {
+    let rom = std::fs::read(rom_path).map_err(|source| MovieError::Io { path: rom_path.to_string(), source })?;
+    Ok(fnv1a(&rom))
+}
+
+// std's hashers may change between Rust versions, movies must not
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xCBF29CE484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001B3))
+}
@@ -0,0 +1,213 @@
+// Read-only views of PPU memory, meant for debugging tools rather than emulation.
+use super::{apply_palette, Ppu};
+use crate::gameboy::GameBoy;
+
+pub const TILE_COUNT: usize = 384;
+pub const TILES_PER_ROW: usize = 16;
+pub const TILE_DATA_WIDTH: usize = TILES_PER_ROW * 8;
+pub const TILE_DATA_HEIGHT: usize = (TILE_COUNT / TILES_PER_ROW) * 8;
+
+pub const TILEMAP_SIZE: usize = 256;
+pub const TILEMAP_BASES: [u16; 2] = [0x9800, 0x9C00];
+
+pub const OAM_ENTRIES: usize = 40;
+
+// An OAM entry, as written by the game
+#[derive(Copy, Clone)]
+pub struct ObjectInfo {
+    pub y: u8,
+    pub x: u8,
+    pub tile: u8,
+    pub flags: u8,
+}
+
+impl ObjectInfo {
+    pub fn bg_priority(&self) -> bool {
+        self.flags & 0x80 != 0
+    }
+
+    pub fn y_flip(&self) -> bool {
+        self.flags & 0x40 != 0
+    }
+
+    pub fn x_flip(&self) -> bool {
+        self.flags & 0x20 != 0
+    }
+
+    pub fn palette(&self) -> Palette {
+        match self.flags & 0x10 != 0 {
+            false => Palette::OBP0,
+            true => Palette::OBP1,
+        }
+    }
+}
+
+// Registers deciding which parts of the tilemaps end up on screen
+pub struct ScrollState {
+    pub scx: u8,
+    pub scy: u8,
+    pub wx: u8,
+    pub wy: u8,
+    pub bg_map: u16,
+    pub win_map: u16,
+    pub win_enabled: bool,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum Palette {
+    BGP,
+    OBP0,
+    OBP1,
+}
+
+impl Palette {
+    pub fn next(self) -> Self {
+        match self {
+            Palette::BGP => Palette::OBP0,
+            Palette::OBP0 => Palette::OBP1,
+            Palette::OBP1 => Palette::BGP,
+        }
+    }
+}
+
+#[inline(always)]
+pub fn tile_addr(index: usize) -> u16 {
+    0x8000 + (index as u16) * 16
+}
+
+impl Ppu {
+    // color ids of a row of pixels of the tile at tile_addr, leftmost pixel first
+    pub(super) fn decode_tile_row(&self, tile_addr: u16, row: u16) -> [u8; 8] {
+        let lo = self.vram.read(tile_addr + row * 2);
+        let hi = self.vram.read(tile_addr + row * 2 + 1);
+        let mut pixels = [0; 8];
+        for (i, pixel) in pixels.iter_mut().enumerate() {
+            *pixel = ((lo >> (7 - i)) & 0x01) | (((hi >> (7 - i)) & 0x01) << 1);
+        }
+        pixels
+    }
+
+    // address of the data of a background or window tile, following LCDC's
+    // tile data addressing mode
+    fn bg_tile_addr(&self, tile_id: u8) -> u16 {
+        match (self.lcdc_td_sel(), tile_id >= 128) {
+            (true, _) => 0x8000 + tile_id as u16 * 16,
+            (false, false) => 0x9000 + tile_id as u16 * 16,
+            (false, true) => 0x8800 + (tile_id - 128) as u16 * 16,
+        }
+    }
+
+    pub(super) fn palette(&self, palette: Palette) -> u8 {
+        match palette {
+            Palette::BGP => self.bgp,
+            Palette::OBP0 => self.obp0,
+            Palette::OBP1 => self.obp1,
+        }
+    }
+}
+
+impl GameBoy {
+    // All tiles in VRAM, laid out in rows of TILES_PER_ROW tiles, as shades
+    // ready to be displayed like the framebuffer
+    pub fn tile_data(&self, palette: Palette) -> Vec<u8> {
+        let palette = self.ppu.palette(palette);
+        let mut buf = vec![0; TILE_DATA_WIDTH * TILE_DATA_HEIGHT];
+        for index in 0..TILE_COUNT {
+            let (tile_x, tile_y) = ((index % TILES_PER_ROW) * 8, (index / TILES_PER_ROW) * 8);
+            for row in 0..8 {
+                let pixels = self.ppu.decode_tile_row(tile_addr(index), row as u16);
+                for (col, pixel) in pixels.iter().enumerate() {
+                    buf[(tile_y + row) * TILE_DATA_WIDTH + tile_x + col] = apply_palette(*pixel, palette);
+                }
+            }
+        }
+        buf
+    }
+}
+
+impl GameBoy {
+    // The 32x32 tilemap at base (one of TILEMAP_BASES), decoded with the
+    // current tile data addressing mode and BGP
+    pub fn tilemap(&self, base: u16) -> Vec<u8> {
+        let mut buf = vec![0; TILEMAP_SIZE * TILEMAP_SIZE];
+        for tile in 0..32 * 32 {
+            let tile_id = self.ppu.vram.read(base + tile as u16);
+            let (tile_x, tile_y) = ((tile % 32) * 8, (tile / 32) * 8);
+            for row in 0..8 {
+                let pixels = self.ppu.decode_tile_row(self.ppu.bg_tile_addr(tile_id), row as u16);
+                for (col, pixel) in pixels.iter().enumerate() {
+                    buf[(tile_y + row) * TILEMAP_SIZE + tile_x + col] = apply_palette(*pixel, self.ppu.bgp);
+                }
+            }
+        }
+        buf
+    }
+
+    // The raw tile IDs of the tilemap at base, readable even while the PPU
+    // locks VRAM
+    pub fn tilemap_ids(&self, base: u16) -> Vec<u8> {
+        (0..32 * 32).map(|tile| self.ppu.vram.read(base + tile)).collect()
+    }
+
+    pub fn scroll_state(&self) -> ScrollState {
+        ScrollState {
+            scx: self.ppu.scx,
+            scy: self.ppu.scy,
+            wx: self.ppu.wx,
+            wy: self.ppu.wy,
+            bg_map: TILEMAP_BASES[self.ppu.lcdc_bg_tm_sel() as usize],
+            win_map: TILEMAP_BASES[self.ppu.lcdc_wn_tm_sel() as usize],
+            win_enabled: self.ppu.lcdc_wn_enbl(),
+        }
+    }
+}
+
+impl GameBoy {
+    pub fn oam_entries(&self) -> Vec<ObjectInfo> {
+        (0..OAM_ENTRIES as u16)
+            .map(|index| ObjectInfo {
+                y: self.ppu.oam.read(index * 4),
+                x: self.ppu.oam.read(index * 4 + 1),
+                tile: self.ppu.oam.read(index * 4 + 2),
+                flags: self.ppu.oam.read(index * 4 + 3),
+            })
+            .collect()
+    }
+
+    // 8 or 16, following LCDC
+    pub fn object_height(&self) -> usize {
+        if self.ppu.lcdc_sp_size() {
+            16
+        } else {
+            8
+        }
+    }
+
+    // An object as it would be drawn, flips and palette applied, 8 pixels wide
+    // and object_height() tall. Transparent pixels are None.
+    pub fn object_image(&self, obj: &ObjectInfo) -> Vec<Option<u8>> {
+        let height = self.object_height();
+        let tile = if height == 16 { obj.tile & !0x01 } else { obj.tile };
+        let palette = self.ppu.palette(obj.palette());
+
+        let mut buf = vec![None; 8 * height];
+        for row in 0..height {
+            let src_row = if obj.y_flip() { height - 1 - row } else { row };
+            let pixels = self.ppu.decode_tile_row(tile_addr(tile as usize), src_row as u16);
+            for (col, pixel) in pixels.iter().enumerate() {
+                let col = if obj.x_flip() { 7 - col } else { col };
+                if *pixel != 0 {
+                    buf[row * 8 + col] = Some(apply_palette(*pixel, palette));
+                }
+            }
+        }
+        buf
+    }
+
+    // OAM indexes of the objects found on a line during its last OAM scan,
+    // as bitmasks of those selected for drawing and those dropped for
+    // exceeding the 10 objects per line limit
+    pub fn line_objects(&self, line: usize) -> (u64, u64) {
+        self.ppu.sp.line_objects(line)
+    }
+}
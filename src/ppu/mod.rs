@@ -1,12 +1,14 @@
 use crate::gameboy::GameBoy;
 use crate::intr::Interrupt;
 use background::Background;
+use inspect::Palette;
 use sprites::Sprites;
 
 use oam::Oam;
 use vram::VRam;
 
 mod background;
+pub mod inspect;
 mod lcd;
 mod oam;
 mod sprites;
@@ -50,6 +52,14 @@ pub struct Ppu {
     cycles: u32,
 
     framebuffer: [u8; NCOL * NLIN],
+    layerbuffer: [Layer; NCOL * NLIN],
+    palettebuffer: [Palette; NCOL * NLIN],
+    // copies of the last fully drawn frame, for screenshots taken mid-frame
+    lastframebuffer: [u8; NCOL * NLIN],
+    lastpalettebuffer: [Palette; NCOL * NLIN],
+    layers_shown: [bool; 3],
+    frames: u64,
+    idle_cycles: u32,
     lcd_status: LcdStatus,
 }
 
@@ -61,6 +71,14 @@ enum PpuMode {
     DRAW = 3,
 }
 
+// Where a pixel of the framebuffer came from
+#[derive(Copy, Clone, Debug)]
+pub enum Layer {
+    BACKGROUND = 0,
+    WINDOW = 1,
+    SPRITES = 2,
+}
+
 #[derive(Copy, Clone, Debug)]
 enum LcdStatus {
     ON,
@@ -162,6 +180,50 @@ impl GameBoy {
     pub fn borrow_framebuffer(&self) -> &[u8; NCOL * NLIN] {
         &self.ppu.framebuffer
     }
+
+    pub fn borrow_layerbuffer(&self) -> &[Layer; NCOL * NLIN] {
+        &self.ppu.layerbuffer
+    }
+
+    // Saves the last completed frame as a PNG, colored by the frontend, which
+    // decides what each shade looks like after going through each palette
+    pub fn save_screenshot(&self, path: &str, color: impl Fn(Palette, u8) -> [u8; 3]) -> image::ImageResult<()> {
+        let mut rgb = vec![0; NCOL * NLIN * 3];
+        for (i, pixel) in self.ppu.lastframebuffer.iter().enumerate() {
+            rgb[i * 3..i * 3 + 3].copy_from_slice(&color(self.ppu.lastpalettebuffer[i], *pixel));
+        }
+        image::save_buffer(path, &rgb, NCOL as u32, NLIN as u32, image::ColorType::Rgb8)
+    }
+
+    // Saves the last completed frame's shades as they are, one 0-3 value per
+    // pixel in a greyscale PNG, meant for test fixtures
+    pub fn save_raw_screenshot(&self, path: &str) -> image::ImageResult<()> {
+        let shades: Vec<u8> = self.ppu.lastframebuffer.iter().map(|pixel| pixel & 0x03).collect();
+        image::save_buffer(path, &shades, NCOL as u32, NLIN as u32, image::ColorType::L8)
+    }
+
+    // Palette register each pixel of the framebuffer went through, so
+    // frontends can color objects differently from the background
+    pub fn borrow_palettebuffer(&self) -> &[Palette; NCOL * NLIN] {
+        &self.ppu.palettebuffer
+    }
+
+    // Hiding a layer only affects what ends up in the framebuffer, the PPU
+    // still fetches it and takes as long as usual to draw each line
+    pub fn set_layer_shown(&mut self, layer: Layer, shown: bool) {
+        self.ppu.layers_shown[layer as usize] = shown;
+    }
+
+    pub fn layer_shown(&self, layer: Layer) -> bool {
+        self.ppu.layers_shown[layer as usize]
+    }
+
+    // number of frames completed since power on, useful to know when the
+    // framebuffer has been fully drawn. Frames keep being counted while the
+    // LCD is off, as if it were drawing.
+    pub fn frame_count(&self) -> u64 {
+        self.ppu.frames
+    }
 }
 
 macro_rules! bit_access {
@@ -209,6 +271,13 @@ impl Ppu {
             cycles: 0,
 
             framebuffer: [0; NLIN * NCOL],
+            layerbuffer: [Layer::BACKGROUND; NLIN * NCOL],
+            palettebuffer: [Palette::BGP; NLIN * NCOL],
+            lastframebuffer: [0; NLIN * NCOL],
+            lastpalettebuffer: [Palette::BGP; NLIN * NCOL],
+            layers_shown: [true; 3],
+            frames: 0,
+            idle_cycles: 0,
             lcd_status: LcdStatus::ON,
         }
     }
@@ -287,8 +356,19 @@ impl Ppu {
         }
     }
 
+    // Time passing while the PPU doesn't draw, with the LCD off or in STOP
+    // mode, still counts towards frames so frontends keep ticking
+    pub fn idle(&mut self, cycles: u8) {
+        self.idle_cycles += cycles as u32;
+        if self.idle_cycles >= 70224 {
+            self.idle_cycles -= 70224;
+            self.frames += 1;
+        }
+    }
+
     fn cycle(&mut self) {
         if let LcdStatus::OFF = self.lcd_status {
+            self.idle(1);
             return;
         };
         self.cycles += 1;
@@ -298,6 +378,9 @@ impl Ppu {
                     self.cycles = 0;
                     self.ly += 1;
                     if self.ly == 144 {
+                        self.frames += 1;
+                        self.lastframebuffer = self.framebuffer;
+                        self.lastpalettebuffer = self.palettebuffer;
                         self.init_frame_bg();
                         self.set_mode(PpuMode::VBLANK);
                     } else {
@@ -343,6 +426,9 @@ impl Ppu {
                 self.draw_pixel();
 
                 if self.lx == 160 {
+                    if let LcdStatus::ON = self.lcd_status {
+                        self.draw_extra_objects();
+                    }
                     self.set_mode(PpuMode::HBLANK);
                 }
             }
@@ -354,11 +440,13 @@ impl Ppu {
         if self.sp.is_fetching() || self.check_in_win() {
             return;
         }
-        _ = self.mix_pixel().and_then(|pixel| {
+        _ = self.mix_pixel().and_then(|(pixel, layer, palette)| {
             let idx = self.ly as usize * 160 + self.lx as usize;
             // first frame after turning lcd on gets skipped
             if let LcdStatus::ON = self.lcd_status {
                 self.framebuffer[idx] = pixel;
+                self.layerbuffer[idx] = layer;
+                self.palettebuffer[idx] = palette;
             }
             self.lx += 1;
             self.fetch_obj();
@@ -366,15 +454,25 @@ impl Ppu {
         });
     }
 
-    fn mix_pixel(&mut self) -> Option<u8> {
-        let bg_pixel = self.bg_pop()?;
-        let (sp_pixel, bg_priority, sp_palette) = self.sp_pop().unwrap_or((0, false, 0));
+    fn mix_pixel(&mut self) -> Option<(u8, Layer, Palette)> {
+        let mut bg_pixel = self.bg_pop()?;
+        let (mut sp_pixel, bg_priority, sp_palette) = self.sp_pop().unwrap_or((0, false, Palette::OBP0));
+
+        let bg_layer = if self.bg.win_mode { Layer::WINDOW } else { Layer::BACKGROUND };
+        if !self.layers_shown[bg_layer as usize] {
+            bg_pixel = 0;
+        }
+        // extra objects go behind the background as shown, like the others
+        self.set_line_pixel(bg_pixel, sp_pixel);
+        if !self.layers_shown[Layer::SPRITES as usize] {
+            sp_pixel = 0;
+        }
 
         if sp_pixel == 0 || (bg_priority && bg_pixel != 0) {
-            return Some(apply_palette(bg_pixel, self.bgp));
+            return Some((apply_palette(bg_pixel, self.bgp), bg_layer, Palette::BGP));
         }
 
-        Some(apply_palette(sp_pixel, sp_palette))
+        Some((apply_palette(sp_pixel, self.palette(sp_palette)), Layer::SPRITES, sp_palette))
     }
 }
 
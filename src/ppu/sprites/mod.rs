@@ -1,3 +1,4 @@
+use super::inspect::Palette;
 use fifo::Fifo;
 mod fifo;
 
@@ -12,6 +13,17 @@ pub struct Sprites {
     data_hi: u8,
 
     fifo: Fifo,
+
+    // OAM entries matching each line, as bitmasks of their indexes, split in
+    // those that made it into obj_buffer and those left out by the limit
+    selected: [u64; 144],
+    dropped: [u64; 144],
+
+    // Enhancement: objects past the 10 per line limit are still drawn, over
+    // the finished line, so mode 3 lasts exactly as long as on hardware
+    unlimited: bool,
+    extra_objs: Vec<Object>,
+    line_pixels: [(u8, u8); 160], // bg and object color ids drawn on this line
 }
 
 #[derive(Clone, Copy)]
@@ -39,6 +51,11 @@ impl Sprites {
             data_lo: 0,
             data_hi: 0,
             fifo: Fifo::init(),
+            selected: [0; 144],
+            dropped: [0; 144],
+            unlimited: false,
+            extra_objs: vec![],
+            line_pixels: [(0, 0); 160],
         }
     }
 
@@ -48,13 +65,24 @@ impl Sprites {
             _ => true,
         }
     }
+
+    pub fn line_objects(&self, line: usize) -> (u64, u64) {
+        (self.selected[line], self.dropped[line])
+    }
 }
 
 impl super::Ppu {
     pub(super) fn fetch_object(&mut self) {
-        let obj_addr = 0xFE00 + (self.sp.fetcher_idx as u16 * 4);
+        let index = self.sp.fetcher_idx;
+        let obj_addr = 0xFE00 + (index as u16 * 4);
         self.sp.fetcher_idx += 1;
 
+        let line = self.ly as usize;
+        if index == 0 {
+            self.sp.selected[line] = 0;
+            self.sp.dropped[line] = 0;
+        }
+
         let obj = Object {
             y: self.oam.read(obj_addr + 0),
             x: self.oam.read(obj_addr + 1),
@@ -69,12 +97,19 @@ impl super::Ppu {
 
         if self.sp.obj_buffer.len() < 10 {
             self.sp.obj_buffer.push(obj);
+            self.sp.selected[line] |= 1 << index;
+        } else {
+            self.sp.dropped[line] |= 1 << index;
+            if self.sp.unlimited {
+                self.sp.extra_objs.push(obj);
+            }
         }
     }
 
     pub(super) fn clear_sp_fetcher(&mut self) {
         self.sp.fetcher_idx = 0;
         self.sp.obj_buffer.clear();
+        self.sp.extra_objs.clear();
     }
 
     pub(super) fn init_scanline_sp(&mut self) {
@@ -121,14 +156,18 @@ impl super::Ppu {
     }
 
     fn get_sprite_addr(&self) -> u16 {
+        self.object_row_addr(&self.sp.cur_obj)
+    }
+
+    fn object_row_addr(&self, obj: &Object) -> u16 {
         let (obj_height, obj_id) = match self.lcdc_sp_size() {
-            false => (8, self.sp.cur_obj.id),
-            true => (16, self.sp.cur_obj.id & !0x01),
+            false => (8, obj.id),
+            true => (16, obj.id & !0x01),
         };
         let tile_addr = 0x8000 + (obj_id as u16 * 16);
 
-        let mut offset = (self.ly as u16 + 16 - self.sp.cur_obj.y as u16) % obj_height;
-        if self.sp.cur_obj.flags & 0x40 != 0 {
+        let mut offset = (self.ly as u16 + 16 - obj.y as u16) % obj_height;
+        if obj.flags & 0x40 != 0 {
             offset = obj_height - 1 - offset;
         }
 
@@ -136,18 +175,80 @@ impl super::Ppu {
     }
 
     #[inline(always)]
-    pub(super) fn sp_pop(&mut self) -> Option<(u8, bool, u8)> {
+    pub(super) fn sp_pop(&mut self) -> Option<(u8, bool, Palette)> {
         let (pixel, bg_priority, palette_flag) = self.sp.fifo.pop()?;
 
         if !self.lcdc_sp_enbl() {
-            return Some((0, false, 0));
+            return Some((0, false, Palette::OBP0));
         }
 
-        let palette = if palette_flag { self.obp1 } else { self.obp0 };
+        let palette = if palette_flag { Palette::OBP1 } else { Palette::OBP0 };
         Some((pixel, bg_priority, palette))
     }
 }
 
+impl super::Ppu {
+    pub(super) fn set_line_pixel(&mut self, bg_pixel: u8, sp_pixel: u8) {
+        if (self.lx as usize) < 160 {
+            self.sp.line_pixels[self.lx as usize] = (bg_pixel, sp_pixel);
+        }
+    }
+
+    // Draws the objects dropped from this line over it, never covering the
+    // ones the PPU drew itself, which always have priority: lower OAM indexes
+    // win ties, and those came first.
+    pub(super) fn draw_extra_objects(&mut self) {
+        if self.sp.extra_objs.is_empty() || !self.lcdc_sp_enbl() || !self.layers_shown[super::Layer::SPRITES as usize] {
+            return;
+        }
+
+        // on DMG, the object with the smallest X wins
+        let mut objs = std::mem::take(&mut self.sp.extra_objs);
+        objs.sort_by_key(|obj| obj.x);
+
+        for obj in objs.iter() {
+            let addr = self.object_row_addr(obj);
+            let (mut lo, mut hi) = (self.vram.read(addr), self.vram.read(addr + 1));
+            if obj.flags & 0x20 != 0 {
+                lo = mirror_byte(lo);
+                hi = mirror_byte(hi);
+            }
+            let palette = if obj.flags & 0x10 != 0 { Palette::OBP1 } else { Palette::OBP0 };
+
+            for px in 0..8 {
+                let x = obj.x as usize + px;
+                if !(8..168).contains(&x) {
+                    continue;
+                }
+                let x = x - 8;
+
+                let color = ((lo >> (7 - px)) & 0x01) | (((hi >> (7 - px)) & 0x01) << 1);
+                let (bg_pixel, sp_pixel) = self.sp.line_pixels[x];
+                if color == 0 || sp_pixel != 0 {
+                    continue;
+                }
+                self.sp.line_pixels[x].1 = color;
+                if obj.flags & 0x80 != 0 && bg_pixel != 0 {
+                    continue;
+                }
+
+                let idx = self.ly as usize * 160 + x;
+                self.framebuffer[idx] = super::apply_palette(color, self.palette(palette));
+                self.layerbuffer[idx] = super::Layer::SPRITES;
+                self.palettebuffer[idx] = palette;
+            }
+        }
+    }
+}
+
+impl crate::gameboy::GameBoy {
+    // Enhancement removing the 10 objects per line limit, fixing flicker in
+    // games that multiplex objects. Timing stays the same as with the limit.
+    pub fn set_unlimited_sprites(&mut self, unlimited: bool) {
+        self.ppu.sp.unlimited = unlimited;
+    }
+}
+
 #[inline(always)]
 fn mirror_byte(mut byte: u8) -> u8 {
     byte = (byte & 0xF0) >> 4 | (byte & 0x0F) << 4;
@@ -1,3 +1,15 @@
 mod acid;
 mod blargg;
 mod mooneye;
+mod stop;
+
+// A 32KiB ROM only cartridge running program from $0100, for tests that can't
+// use a suite ROM
+#[cfg(test)]
+pub fn load_program(name: &str, program: &[u8]) -> crate::gameboy::GameBoy {
+    let mut rom = vec![0; 0x8000];
+    rom[0x0100..0x0100 + program.len()].copy_from_slice(program);
+    let path = std::env::temp_dir().join(format!("uepa-{}.gb", name));
+    std::fs::write(&path, rom).unwrap();
+    crate::gameboy::GameBoy::init(path.to_str().unwrap())
+}
@@ -58,6 +58,7 @@ mod intr {
     test_mooneye!(ie_push, "acceptance/interrupts/ie_push.gb");
 }
 
+// STOP has no mooneye tests, see stop.rs
 mod halt {
     test_mooneye!(halt_ime0_ei, "acceptance/halt_ime0_ei.gb");
     test_mooneye!(halt_ime0_nointr_timing, "acceptance/halt_ime0_nointr_timing.gb");
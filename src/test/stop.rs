@@ -0,0 +1,64 @@
+#![cfg(test)]
+
+// Neither mooneye nor SameSuite have ROMs for STOP mode or the joypad lines
+// waking it up, so these tests assemble their own tiny programs instead.
+use super::load_program;
+use crate::mmu::io::joypad::Button;
+
+// selects the action buttons, then STOPs with no interrupt pending
+const STOP_PROGRAM: [u8; 10] = [
+    0x3E, 0x10, // ld a, $10
+    0xE0, 0x00, // ldh [rP1], a
+    0x10, 0x00, // stop
+    0x06, 0x42, // ld b, $42
+    0x18, 0xFE, // jr @
+];
+
+#[test]
+fn stop_waits_for_button() {
+    let mut gb = load_program("stop_waits_for_button", &STOP_PROGRAM);
+    for _ in 0..3 {
+        gb.cpu_step();
+    }
+    assert!(gb.stop, "STOP mode wasn't entered.");
+    assert_eq!(gb.timer.read_div(), 0, "DIV wasn't reset.");
+
+    for _ in 0..100000 {
+        gb.cpu_step();
+    }
+    assert!(gb.stop, "STOP mode ended without a button press.");
+    assert_eq!(gb.timer.read_div(), 0, "DIV kept counting in STOP mode.");
+
+    gb.set_button(Button::A, true);
+    for _ in 0..3 {
+        gb.cpu_step();
+    }
+    assert!(!gb.stop, "Pressing a selected button didn't end STOP mode.");
+    assert_eq!(gb.cpu.b, 0x42, "The byte after STOP wasn't skipped.");
+}
+
+#[test]
+fn stop_ignores_unselected_buttons() {
+    let mut gb = load_program("stop_ignores_unselected_buttons", &STOP_PROGRAM);
+    for _ in 0..3 {
+        gb.cpu_step();
+    }
+
+    gb.set_button(Button::UP, true);
+    for _ in 0..1000 {
+        gb.cpu_step();
+    }
+    assert!(gb.stop, "A button outside the selected group ended STOP mode.");
+}
+
+#[test]
+fn stop_with_button_held_halts() {
+    let mut gb = load_program("stop_with_button_held_halts", &STOP_PROGRAM);
+    gb.set_button(Button::A, true);
+    for _ in 0..3 {
+        gb.cpu_step();
+    }
+    assert!(!gb.stop, "STOP mode was entered with a button held.");
+    assert!(gb.halt, "STOP didn't turn into HALT with a button held.");
+    assert_eq!(gb.cpu.pc, 0x0106, "The byte after STOP wasn't skipped.");
+}
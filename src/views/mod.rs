@@ -0,0 +1,58 @@
+// Debugging windows shown next to the emulator's main window.
+use crate::{display::palettes::GREYSCALE, gameboy::GameBoy};
+use enum_dispatch::enum_dispatch;
+pub use objects::ObjectView;
+use sdl2::{
+    event::Event,
+    pixels::PixelFormatEnum,
+    render::{Canvas, TextureAccess},
+    video::Window,
+    Sdl,
+};
+pub use tilemaps::TilemapView;
+pub use tiles::TileView;
+
+mod objects;
+mod tilemaps;
+mod tiles;
+
+#[allow(clippy::enum_variant_names)]
+#[enum_dispatch]
+pub enum ViewEnum {
+    ObjectView,
+    TilemapView,
+    TileView,
+}
+
+#[enum_dispatch(ViewEnum)]
+pub trait ViewTrait {
+    fn window_id(&self) -> u32;
+    fn handle_event(&mut self, event: &Event);
+    fn update(&mut self, gb: &GameBoy);
+}
+
+fn init_canvas(sdl: &Sdl, title: &str, width: u32, height: u32) -> Canvas<Window> {
+    let video = sdl.video().unwrap();
+    let window = video.window(title, width, height).build().unwrap();
+    window.into_canvas().build().unwrap()
+}
+
+// converts a buffer of shades, like the framebuffer, to RGB
+fn to_rgb(buf: &[u8]) -> Vec<u8> {
+    let mut rgb = vec![0; buf.len() * 3];
+    for (i, pixel) in buf.iter().enumerate() {
+        rgb[i * 3..i * 3 + 3].copy_from_slice(&GREYSCALE[*pixel as usize]);
+    }
+    rgb
+}
+
+// stretches an RGB buffer over the whole window
+fn present(canvas: &mut Canvas<Window>, rgb: &[u8], width: usize, height: usize) {
+    let tex_creator = canvas.texture_creator();
+    let mut tex = tex_creator
+        .create_texture(PixelFormatEnum::RGB24, TextureAccess::Streaming, width as u32, height as u32)
+        .unwrap();
+    tex.update(None, rgb, width * 3).unwrap();
+    canvas.copy(&tex, None, None).unwrap();
+    canvas.present();
+}
@@ -0,0 +1,158 @@
+use super::{init_canvas, present, ViewTrait};
+use crate::{
+    display::palettes::GREYSCALE,
+    gameboy::GameBoy,
+    ppu::inspect::{ObjectInfo, OAM_ENTRIES},
+};
+use sdl2::{
+    event::{Event, WindowEvent},
+    render::Canvas,
+    video::Window,
+    Sdl,
+};
+
+const SCALE: u32 = 3;
+const COLUMNS: usize = 8;
+const CELL_WIDTH: usize = 12;
+const CELL_HEIGHT: usize = 20; // fits 8x16 objects
+const GRID_WIDTH: usize = COLUMNS * CELL_WIDTH;
+const GAP: usize = 4;
+const LINES_X: usize = GRID_WIDTH + GAP; // where the scanline panel starts
+const WIDTH: usize = LINES_X + OAM_ENTRIES;
+const HEIGHT: usize = 144;
+
+const BACKGROUND_COLOR: [u8; 3] = [0x40, 0x40, 0x40];
+const TRANSPARENT_COLOR: [u8; 3] = [0xFF, 0x00, 0xFF];
+const SELECTED_COLOR: [u8; 3] = [0x00, 0xC0, 0x00];
+const DROPPED_COLOR: [u8; 3] = [0xFF, 0x00, 0x00];
+const EMPTY_COLOR: [u8; 3] = [0x20, 0x20, 0x20];
+
+enum Hovered {
+    Object(usize),
+    Line(usize),
+}
+
+// Shows the 40 OAM entries on the left, transparent pixels in magenta, and to
+// their right one row per scanline with a column per entry: green for objects
+// drawn on that line, red for the ones dropped by the 10 per line limit.
+// Hovering an object shows its attributes, hovering a line lists its objects.
+pub struct ObjectView {
+    canvas: Canvas<Window>,
+    hovered: Option<Hovered>,
+    objects: Vec<ObjectInfo>,
+    lines: Vec<(u64, u64)>,
+}
+
+impl ObjectView {
+    pub fn init(sdl: &Sdl) -> Self {
+        let canvas = init_canvas(sdl, "Objects", WIDTH as u32 * SCALE, HEIGHT as u32 * SCALE);
+        let mut view = Self { canvas, hovered: None, objects: vec![], lines: vec![] };
+        view.update_title();
+        view
+    }
+
+    fn update_title(&mut self) {
+        let title = match self.hovered {
+            Some(Hovered::Object(index)) if index < self.objects.len() => {
+                let obj = &self.objects[index];
+                format!(
+                    "Objects - #{} X {} Y {} tile ${:02X} {:?}{}{}{}",
+                    index,
+                    obj.x,
+                    obj.y,
+                    obj.tile,
+                    obj.palette(),
+                    if obj.x_flip() { " X-flip" } else { "" },
+                    if obj.y_flip() { " Y-flip" } else { "" },
+                    if obj.bg_priority() { " behind BG" } else { "" },
+                )
+            }
+            Some(Hovered::Line(line)) if line < self.lines.len() => {
+                let (selected, dropped) = self.lines[line];
+                format!("Objects - line {}: drawn {:?} dropped {:?}", line, indexes(selected), indexes(dropped))
+            }
+            _ => "Objects".to_string(),
+        };
+        _ = self.canvas.window_mut().set_title(&title);
+    }
+}
+
+impl ViewTrait for ObjectView {
+    fn window_id(&self) -> u32 {
+        self.canvas.window().id()
+    }
+
+    fn handle_event(&mut self, event: &Event) {
+        if event.get_window_id() != Some(self.window_id()) {
+            return;
+        }
+
+        match event {
+            Event::MouseMotion { x, y, .. } => {
+                let (x, y) = ((*x as u32 / SCALE) as usize, (*y as u32 / SCALE) as usize);
+                let index = (y / CELL_HEIGHT) * COLUMNS + x / CELL_WIDTH;
+                self.hovered = match x {
+                    _ if y >= HEIGHT => None,
+                    _ if x < GRID_WIDTH && index < OAM_ENTRIES => Some(Hovered::Object(index)),
+                    _ if (LINES_X..WIDTH).contains(&x) => Some(Hovered::Line(y)),
+                    _ => None,
+                };
+                self.update_title();
+            }
+            Event::Window { win_event: WindowEvent::Leave, .. } => {
+                self.hovered = None;
+                self.update_title();
+            }
+            Event::Window { win_event: WindowEvent::Close, .. } => self.canvas.window_mut().hide(),
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, gb: &GameBoy) {
+        let mut rgb = vec![0; WIDTH * HEIGHT * 3];
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                put_pixel(&mut rgb, x, y, BACKGROUND_COLOR);
+            }
+        }
+
+        self.objects = gb.oam_entries();
+        let height = gb.object_height();
+        for (index, obj) in self.objects.iter().enumerate() {
+            let cell_x = (index % COLUMNS) * CELL_WIDTH + (CELL_WIDTH - 8) / 2;
+            let cell_y = (index / COLUMNS) * CELL_HEIGHT + (CELL_HEIGHT - height) / 2;
+            for (i, pixel) in gb.object_image(obj).iter().enumerate() {
+                let color = match pixel {
+                    Some(shade) => GREYSCALE[*shade as usize],
+                    None => TRANSPARENT_COLOR,
+                };
+                put_pixel(&mut rgb, cell_x + i % 8, cell_y + i / 8, color);
+            }
+        }
+
+        self.lines = (0..HEIGHT).map(|line| gb.line_objects(line)).collect();
+        for (line, (selected, dropped)) in self.lines.iter().enumerate() {
+            for index in 0..OAM_ENTRIES {
+                let color = match (selected >> index & 1 != 0, dropped >> index & 1 != 0) {
+                    (true, _) => SELECTED_COLOR,
+                    (_, true) => DROPPED_COLOR,
+                    _ => EMPTY_COLOR,
+                };
+                put_pixel(&mut rgb, LINES_X + index, line, color);
+            }
+        }
+
+        self.update_title();
+        present(&mut self.canvas, &rgb, WIDTH, HEIGHT);
+    }
+}
+
+#[inline(always)]
+fn put_pixel(rgb: &mut [u8], x: usize, y: usize, color: [u8; 3]) {
+    let idx = (y * WIDTH + x) * 3;
+    rgb[idx..idx + 3].copy_from_slice(&color);
+}
+
+fn indexes(mask: u64) -> Vec<usize> {
+    (0..OAM_ENTRIES).filter(|index| mask >> index & 1 != 0).collect()
+}
@@ -0,0 +1,152 @@
+use super::{init_canvas, present, to_rgb, ViewTrait};
+use crate::{
+    gameboy::GameBoy,
+    ppu::inspect::{ScrollState, TILEMAP_BASES, TILEMAP_SIZE},
+};
+use sdl2::{
+    event::{Event, WindowEvent},
+    render::Canvas,
+    video::Window,
+    Sdl,
+};
+
+const SCALE: u32 = 2;
+const GAP: usize = 4; // columns between both maps
+const WIDTH: usize = TILEMAP_SIZE * 2 + GAP;
+const HEIGHT: usize = TILEMAP_SIZE;
+
+const VIEWPORT_COLOR: [u8; 3] = [0xFF, 0x00, 0x00];
+const WINDOW_COLOR: [u8; 3] = [0x00, 0x80, 0xFF];
+const GAP_COLOR: [u8; 3] = [0x80, 0x80, 0x80];
+
+// Shows the tilemaps at $9800 and $9C00 side by side. The area shown on screen
+// by the background (SCX/SCY) is outlined in red, and the area shown by the
+// window (WX/WY) in blue. Hovering a tile shows its address and id.
+pub struct TilemapView {
+    canvas: Canvas<Window>,
+    hovered: Option<(usize, u8, u8)>, // map, tile x, tile y
+    tile_ids: [Vec<u8>; 2],
+    scroll: Option<ScrollState>,
+}
+
+impl TilemapView {
+    pub fn init(sdl: &Sdl) -> Self {
+        let canvas = init_canvas(sdl, "Tilemaps", WIDTH as u32 * SCALE, HEIGHT as u32 * SCALE);
+        let mut view = Self { canvas, hovered: None, tile_ids: [vec![0; 32 * 32], vec![0; 32 * 32]], scroll: None };
+        view.update_title();
+        view
+    }
+
+    fn update_title(&mut self) {
+        let mut title = match &self.scroll {
+            Some(scroll) => format!(
+                "Tilemaps - BG ${:04X} SCX {} SCY {} | WIN ${:04X} WX {} WY {}{}",
+                scroll.bg_map,
+                scroll.scx,
+                scroll.scy,
+                scroll.win_map,
+                scroll.wx,
+                scroll.wy,
+                if scroll.win_enabled { "" } else { " (off)" }
+            ),
+            None => "Tilemaps".to_string(),
+        };
+        if let Some((map, x, y)) = self.hovered {
+            let offset = y as usize * 32 + x as usize;
+            let addr = TILEMAP_BASES[map] + offset as u16;
+            title += &format!(" - ({}, {}) at ${:04X}: tile ${:02X}", x, y, addr, self.tile_ids[map][offset]);
+        }
+        _ = self.canvas.window_mut().set_title(&title);
+    }
+}
+
+impl ViewTrait for TilemapView {
+    fn window_id(&self) -> u32 {
+        self.canvas.window().id()
+    }
+
+    fn handle_event(&mut self, event: &Event) {
+        if event.get_window_id() != Some(self.window_id()) {
+            return;
+        }
+
+        match event {
+            Event::MouseMotion { x, y, .. } => {
+                let (x, y) = ((*x as u32 / SCALE) as usize, (*y as u32 / SCALE) as usize);
+                self.hovered = match x {
+                    _ if y >= HEIGHT => None,
+                    0..=255 => Some((0, (x / 8) as u8, (y / 8) as u8)),
+                    _ if (TILEMAP_SIZE + GAP..WIDTH).contains(&x) => {
+                        Some((1, ((x - TILEMAP_SIZE - GAP) / 8) as u8, (y / 8) as u8))
+                    }
+                    _ => None,
+                };
+                self.update_title();
+            }
+            Event::Window { win_event: WindowEvent::Leave, .. } => {
+                self.hovered = None;
+                self.update_title();
+            }
+            Event::Window { win_event: WindowEvent::Close, .. } => self.canvas.window_mut().hide(),
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, gb: &GameBoy) {
+        let mut rgb = vec![0; WIDTH * HEIGHT * 3];
+        for (map, base) in TILEMAP_BASES.iter().enumerate() {
+            let map_rgb = to_rgb(&gb.tilemap(*base));
+            let origin = map * (TILEMAP_SIZE + GAP);
+            for y in 0..TILEMAP_SIZE {
+                let src = y * TILEMAP_SIZE * 3;
+                let dst = (y * WIDTH + origin) * 3;
+                rgb[dst..dst + TILEMAP_SIZE * 3].copy_from_slice(&map_rgb[src..src + TILEMAP_SIZE * 3]);
+            }
+            self.tile_ids[map] = gb.tilemap_ids(*base);
+        }
+        for y in 0..HEIGHT {
+            for x in TILEMAP_SIZE..TILEMAP_SIZE + GAP {
+                put_pixel(&mut rgb, x, y, GAP_COLOR);
+            }
+        }
+
+        let scroll = gb.scroll_state();
+        let map_origin = |base: u16| if base == TILEMAP_BASES[0] { 0 } else { TILEMAP_SIZE + GAP };
+
+        // the background viewport wraps around the edges of the map
+        let origin = map_origin(scroll.bg_map);
+        draw_rect(&mut rgb, origin, scroll.scx as usize, scroll.scy as usize, 160, 144, VIEWPORT_COLOR);
+
+        // the window always starts drawing from the top left corner of its map
+        let (win_x, win_y) = (u8::saturating_sub(scroll.wx, 7) as usize, scroll.wy as usize);
+        if scroll.win_enabled && win_x < 160 && win_y < 144 {
+            let origin = map_origin(scroll.win_map);
+            draw_rect(&mut rgb, origin, 0, 0, 160 - win_x, 144 - win_y, WINDOW_COLOR);
+        }
+
+        self.scroll = Some(scroll);
+        self.update_title();
+        present(&mut self.canvas, &rgb, WIDTH, HEIGHT);
+    }
+}
+
+#[inline(always)]
+fn put_pixel(rgb: &mut [u8], x: usize, y: usize, color: [u8; 3]) {
+    let idx = (y * WIDTH + x) * 3;
+    rgb[idx..idx + 3].copy_from_slice(&color);
+}
+
+// outlines a rectangle inside the map starting at column origin, wrapping
+// around its edges like the PPU does
+fn draw_rect(rgb: &mut [u8], origin: usize, x: usize, y: usize, w: usize, h: usize, color: [u8; 3]) {
+    for i in 0..w {
+        let px = origin + (x + i) % TILEMAP_SIZE;
+        put_pixel(rgb, px, y % TILEMAP_SIZE, color);
+        put_pixel(rgb, px, (y + h - 1) % TILEMAP_SIZE, color);
+    }
+    for j in 0..h {
+        let py = (y + j) % TILEMAP_SIZE;
+        put_pixel(rgb, origin + x % TILEMAP_SIZE, py, color);
+        put_pixel(rgb, origin + (x + w - 1) % TILEMAP_SIZE, py, color);
+    }
+}
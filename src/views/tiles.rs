@@ -0,0 +1,91 @@
+use super::{init_canvas, present, to_rgb, ViewTrait};
+use crate::{
+    gameboy::GameBoy,
+    ppu::inspect::{tile_addr, Palette, TILES_PER_ROW, TILE_COUNT, TILE_DATA_HEIGHT, TILE_DATA_WIDTH},
+};
+use sdl2::{
+    event::{Event, WindowEvent},
+    keyboard::Keycode,
+    render::Canvas,
+    video::Window,
+    Sdl,
+};
+
+const SCALE: u32 = 3;
+const PNG_PATH: &str = "tiles.png";
+
+// Shows every tile in VRAM. Hovering a tile shows its index and address in the
+// title bar, P cycles between BGP, OBP0 and OBP1, and S saves the view as a PNG.
+pub struct TileView {
+    canvas: Canvas<Window>,
+    palette: Palette,
+    hovered: Option<usize>,
+    rgb: Vec<u8>,
+}
+
+impl TileView {
+    pub fn init(sdl: &Sdl) -> Self {
+        let canvas = init_canvas(sdl, "Tile data", TILE_DATA_WIDTH as u32 * SCALE, TILE_DATA_HEIGHT as u32 * SCALE);
+        let mut view = Self { canvas, palette: Palette::BGP, hovered: None, rgb: vec![] };
+        view.update_title();
+        view
+    }
+
+    fn update_title(&mut self) {
+        let title = match self.hovered {
+            Some(index) => format!("Tile data ({:?}) - tile ${:03X} at ${:04X}", self.palette, index, tile_addr(index)),
+            None => format!("Tile data ({:?})", self.palette),
+        };
+        _ = self.canvas.window_mut().set_title(&title);
+    }
+
+    fn save_png(&self) {
+        match image::save_buffer(
+            PNG_PATH,
+            &self.rgb,
+            TILE_DATA_WIDTH as u32,
+            TILE_DATA_HEIGHT as u32,
+            image::ColorType::Rgb8,
+        ) {
+            Ok(()) => println!("Saved tile data to {}", PNG_PATH),
+            Err(e) => println!("Failed to save tile data: {}", e),
+        }
+    }
+}
+
+impl ViewTrait for TileView {
+    fn window_id(&self) -> u32 {
+        self.canvas.window().id()
+    }
+
+    fn handle_event(&mut self, event: &Event) {
+        if event.get_window_id() != Some(self.window_id()) {
+            return;
+        }
+
+        match event {
+            Event::MouseMotion { x, y, .. } => {
+                let (tile_x, tile_y) = ((*x as u32 / (8 * SCALE)) as usize, (*y as u32 / (8 * SCALE)) as usize);
+                let index = tile_y * TILES_PER_ROW + tile_x;
+                self.hovered = if tile_x < TILES_PER_ROW && index < TILE_COUNT { Some(index) } else { None };
+                self.update_title();
+            }
+            Event::Window { win_event: WindowEvent::Leave, .. } => {
+                self.hovered = None;
+                self.update_title();
+            }
+            Event::Window { win_event: WindowEvent::Close, .. } => self.canvas.window_mut().hide(),
+            Event::KeyDown { keycode: Some(Keycode::P), .. } => {
+                self.palette = self.palette.next();
+                self.update_title();
+            }
+            Event::KeyDown { keycode: Some(Keycode::S), .. } => self.save_png(),
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, gb: &GameBoy) {
+        self.rgb = to_rgb(&gb.tile_data(self.palette));
+        present(&mut self.canvas, &self.rgb, TILE_DATA_WIDTH, TILE_DATA_HEIGHT);
+    }
+}